@@ -0,0 +1,820 @@
+use debouncr::{debounce_4, Edge};
+
+use crate::{
+    display::ClockDisplay,
+    error::RuntimeError,
+    hal::{Button, Buzzer, SerialPort, TimeSource},
+    serial::{GameState, Role, SerialHandler, SerialMsg},
+    time_set::{Increment, TimeSetting},
+};
+
+const LOOP_DELAY: u16 = 5;
+const BUZZER_LENGTH: u16 = 20;
+
+pub enum CountdownResult {
+    FinishedP1,
+    FinishedP2,
+    Paused,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Turn {
+    P1,
+    P2,
+}
+
+/// What a player's [`Increment`] means, or whether the clocks act as an
+/// hourglass instead.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TimeControlMode {
+    /// The increment is added to the player's clock after each of their moves.
+    Fischer,
+    /// After each move, the player gets back however much of the delay they
+    /// used.
+    Bronstein,
+    /// The player's clock doesn't start running until the delay has run out.
+    SimpleDelay,
+    /// Time taken off the running player's clock goes onto their opponent's,
+    /// like sand in an hourglass. There's no increment.
+    Hourglass,
+}
+
+impl TimeControlMode {
+    pub fn next(&self) -> TimeControlMode {
+        match self {
+            TimeControlMode::Fischer => TimeControlMode::Bronstein,
+            TimeControlMode::Bronstein => TimeControlMode::SimpleDelay,
+            TimeControlMode::SimpleDelay => TimeControlMode::Hourglass,
+            TimeControlMode::Hourglass => TimeControlMode::Fischer,
+        }
+    }
+
+    pub fn prev(&self) -> TimeControlMode {
+        match self {
+            TimeControlMode::Fischer => TimeControlMode::Hourglass,
+            TimeControlMode::Bronstein => TimeControlMode::Fischer,
+            TimeControlMode::SimpleDelay => TimeControlMode::Bronstein,
+            TimeControlMode::Hourglass => TimeControlMode::SimpleDelay,
+        }
+    }
+
+    pub fn is_delay(&self) -> bool {
+        *self == TimeControlMode::Bronstein || *self == TimeControlMode::SimpleDelay
+    }
+}
+
+/// The most stages a time control can have after the first one.
+pub const MAX_STAGES: usize = 2;
+
+/// A later stage of a multi-stage time control, such as the "30 SD" in
+/// "40/90 + 30 SD".
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Stage {
+    /// The number of moves a player has to make in the previous stage to get
+    /// here
+    pub after_moves: u8,
+    /// Added to a player's clock when they get here
+    pub time: TimeSetting,
+    /// The increment or delay for the rest of the stage
+    pub increment: Increment,
+}
+
+impl Stage {
+    pub fn new() -> Stage {
+        Stage {
+            after_moves: 0,
+            time: TimeSetting::new(0),
+            increment: Increment::new(0),
+        }
+    }
+}
+
+/// What happens once a player's main time runs out.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum OvertimeMode {
+    /// They lose on time.
+    None,
+    /// Japanese byo-yomi: the player gets `count` periods of `period` each. A
+    /// period is only used up if the player overruns it.
+    ByoYomi,
+    /// Canadian overtime: the player has to make `count` moves within
+    /// `period`, after which they get a fresh block.
+    Canadian,
+}
+
+impl OvertimeMode {
+    pub fn next(&self) -> OvertimeMode {
+        match self {
+            OvertimeMode::None => OvertimeMode::ByoYomi,
+            OvertimeMode::ByoYomi => OvertimeMode::Canadian,
+            OvertimeMode::Canadian => OvertimeMode::None,
+        }
+    }
+
+    pub fn prev(&self) -> OvertimeMode {
+        match self {
+            OvertimeMode::None => OvertimeMode::Canadian,
+            OvertimeMode::ByoYomi => OvertimeMode::None,
+            OvertimeMode::Canadian => OvertimeMode::ByoYomi,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Overtime {
+    pub mode: OvertimeMode,
+    /// The number of byo-yomi periods, or the number of moves in a Canadian
+    /// block
+    pub count: u8,
+    /// The length of a byo-yomi period or a Canadian block
+    pub period: TimeSetting,
+}
+
+impl Overtime {
+    /// Makes the usual overtime for `mode`: 5 x 30s for byo-yomi and 20 moves
+    /// in 5 minutes for Canadian.
+    pub fn new(mode: OvertimeMode) -> Overtime {
+        match mode {
+            OvertimeMode::None | OvertimeMode::ByoYomi => Overtime {
+                mode,
+                count: 5,
+                period: TimeSetting::new(30),
+            },
+            OvertimeMode::Canadian => Overtime {
+                mode,
+                count: 20,
+                period: TimeSetting::new(5 * 60),
+            },
+        }
+    }
+}
+
+/// Which part of the time control a player's clock is counting down.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Phase {
+    /// The player is still on their main time.
+    Main,
+    /// The player is in byo-yomi with `periods` periods left, including the
+    /// current one.
+    ByoYomi { periods: u8 },
+    /// The player is in Canadian overtime with `moves` moves left to make in
+    /// the current block.
+    Canadian { moves: u8 },
+}
+
+impl Phase {
+    /// Moves a player on to their next period of overtime once their current
+    /// one (or their main time) runs out. Returns the ms they have in it, or
+    /// `None` if they've lost on time.
+    fn next_period(&mut self, overtime: &Overtime) -> Option<u32> {
+        match (*self, overtime.mode) {
+            (_, OvertimeMode::None) => None,
+            (Phase::Main, OvertimeMode::ByoYomi) => {
+                *self = Phase::ByoYomi {
+                    periods: overtime.count,
+                };
+                Some(overtime.period.into_millis())
+            }
+            (Phase::ByoYomi { periods }, OvertimeMode::ByoYomi) => {
+                if periods <= 1 {
+                    return None;
+                }
+                *self = Phase::ByoYomi {
+                    periods: periods - 1,
+                };
+                Some(overtime.period.into_millis())
+            }
+            (Phase::Main, OvertimeMode::Canadian) => {
+                *self = Phase::Canadian {
+                    moves: overtime.count,
+                };
+                Some(overtime.period.into_millis())
+            }
+            // Running out of time in a Canadian block loses
+            (_, OvertimeMode::ByoYomi | OvertimeMode::Canadian) => None,
+        }
+    }
+
+    /// The ms a player in overtime has left after a move made in time with
+    /// `ms` left on their clock.
+    fn after_move(&mut self, overtime: &Overtime, ms: u32) -> u32 {
+        match *self {
+            Phase::Canadian { moves } if moves > 1 => {
+                *self = Phase::Canadian { moves: moves - 1 };
+                ms
+            }
+            // The block is done, so the player gets a new one
+            Phase::Canadian { moves: _ } => {
+                *self = Phase::Canadian {
+                    moves: overtime.count,
+                };
+                overtime.period.into_millis()
+            }
+            // A move made in time means the next period starts afresh
+            Phase::Main | Phase::ByoYomi { periods: _ } => overtime.period.into_millis(),
+        }
+    }
+}
+
+/// Everything about how time is handed out besides the starting times.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TimeControl {
+    pub mode: TimeControlMode,
+    /// The increment or delay of P1 and P2 in the first stage, depending on
+    /// `mode`
+    pub increments: (Increment, Increment),
+    /// The stages after the first one. Only the first `stage_count` are used.
+    pub stages: [Stage; MAX_STAGES],
+    pub stage_count: u8,
+    pub overtime: Overtime,
+}
+
+impl TimeControl {
+    /// A plain countdown with no increment, stages or overtime.
+    pub fn new() -> TimeControl {
+        TimeControl {
+            mode: TimeControlMode::Fischer,
+            increments: (Increment::new(0), Increment::new(0)),
+            stages: [Stage::new(); MAX_STAGES],
+            stage_count: 0,
+            overtime: Overtime::new(OvertimeMode::None),
+        }
+    }
+
+    /// The stage a player is in after making `moves` moves, where 0 is the
+    /// first stage.
+    pub fn stage(&self, moves: u16) -> usize {
+        let mut threshold: u16 = 0;
+        let mut stage = 0;
+        for next in &self.stages[..self.stage_count as usize] {
+            threshold += next.after_moves as u16;
+            if moves < threshold {
+                break;
+            }
+            stage += 1;
+        }
+        stage
+    }
+
+    /// The stage a player enters with their `moves`th move, if any.
+    pub fn stage_reached(&self, moves: u16) -> Option<usize> {
+        let stage = self.stage(moves);
+        if stage != 0 && self.stage(moves - 1) != stage {
+            Some(stage)
+        } else {
+            None
+        }
+    }
+
+    /// The increment or delay of a player who has made `moves` moves.
+    pub fn increment(&self, turn: &Turn, moves: u16) -> &Increment {
+        match (self.stage(moves), turn) {
+            (0, Turn::P1) => &self.increments.0,
+            (0, Turn::P2) => &self.increments.1,
+            (stage, _) => &self.stages[stage - 1].increment,
+        }
+    }
+
+    /// The increments or delays both players get on their next move.
+    pub fn increments(&self, moves: &(u16, u16)) -> (&Increment, &Increment) {
+        (
+            self.increment(&Turn::P1, moves.0),
+            self.increment(&Turn::P2, moves.1),
+        )
+    }
+}
+
+pub fn countdown<
+    DP: Button,
+    UP: Button,
+    SP: Button,
+    BP: Buzzer,
+    S: SerialPort,
+    T: TimeSource,
+    D: ClockDisplay,
+>(
+    down_pin: &mut DP,
+    up_pin: &mut UP,
+    start_pin: &mut SP,
+    buzzer_pin: &mut BP,
+    serial_handler: &mut SerialHandler<S>,
+    time: &mut T,
+    display: &mut D,
+    p1_time: &mut TimeSetting,
+    p2_time: &mut TimeSetting,
+    control: &TimeControl,
+    moves: &mut (u16, u16),
+    phases: &mut (Phase, Phase),
+    turn: &mut Turn,
+) -> Result<CountdownResult, RuntimeError> {
+    // The button which resumed the game is still held down, and mustn't count
+    // as a move
+    let mut down = debounce_4(
+        down_pin
+            .is_pressed()
+            .map_err(|_| RuntimeError::PinReadError)?,
+    );
+    let mut up = debounce_4(
+        up_pin
+            .is_pressed()
+            .map_err(|_| RuntimeError::PinReadError)?,
+    );
+    let mut start = debounce_4(false);
+
+    // Initialize last_* variable with bogus values to prompt immediate render
+    let mut last_p1_time = TimeSetting::new(u16::MAX);
+    let mut p1_ms_at_change = p1_time.into_millis();
+    let mut last_p2_time = TimeSetting::new(u16::MAX);
+    let mut p2_ms_at_change = p2_time.into_millis();
+    let mut last_turn = turn.clone();
+    let mut last_phases = *phases;
+    let mut last_delay = None;
+    // The stage the waiting player just got to, if they did on their last move
+    let mut stage_note = None;
+    let mut last_stage_note = None;
+    let mut last_moves = None;
+
+    let mut last_change_time = time.millis();
+    let mut remaining_buzzer_duration: u16 = 0;
+    Ok(loop {
+        let now = time.millis();
+        let time_since_change = now - last_change_time;
+        let (increment, in_overtime) = match *turn {
+            Turn::P1 => (control.increment(turn, moves.0), phases.0 != Phase::Main),
+            Turn::P2 => (control.increment(turn, moves.1), phases.1 != Phase::Main),
+        };
+        // Increments and delays only apply to the main time
+        let charged = if in_overtime {
+            time_since_change
+        } else {
+            charged_time(time_since_change, control.mode, increment)
+        };
+        let hourglass = control.mode == TimeControlMode::Hourglass;
+        let new_p1_ms = if *turn == Turn::P1 {
+            match p1_ms_at_change.checked_sub(charged) {
+                Some(x) => x,
+                None => match phases.0.next_period(&control.overtime) {
+                    Some(period_ms) => {
+                        // Carry on in overtime from when the old period ran out
+                        last_change_time = now - (charged - p1_ms_at_change);
+                        p1_ms_at_change = period_ms;
+                        serial_handler.write(SerialMsg::Sync {
+                            p1_time: p1_ms_at_change,
+                            p2_time: p2_ms_at_change,
+                        });
+                        continue;
+                    }
+                    None => {
+                        if hourglass {
+                            // All of the sand has run over to P2
+                            p2_ms_at_change += p1_ms_at_change;
+                        }
+                        p1_ms_at_change = 0;
+                        break finish_countdown(p1_ms_at_change, p2_ms_at_change, p1_time, p2_time);
+                    }
+                },
+            }
+        } else if hourglass {
+            p1_ms_at_change + charged
+        } else {
+            p1_ms_at_change
+        };
+        let new_p1_time = TimeSetting::from_millis(new_p1_ms);
+        let new_p2_ms = if *turn == Turn::P2 {
+            match p2_ms_at_change.checked_sub(charged) {
+                Some(x) => x,
+                None => match phases.1.next_period(&control.overtime) {
+                    Some(period_ms) => {
+                        // Carry on in overtime from when the old period ran out
+                        last_change_time = now - (charged - p2_ms_at_change);
+                        p2_ms_at_change = period_ms;
+                        serial_handler.write(SerialMsg::Sync {
+                            p1_time: p1_ms_at_change,
+                            p2_time: p2_ms_at_change,
+                        });
+                        continue;
+                    }
+                    None => {
+                        if hourglass {
+                            // All of the sand has run over to P1
+                            p1_ms_at_change += p2_ms_at_change;
+                        }
+                        p2_ms_at_change = 0;
+                        break finish_countdown(p1_ms_at_change, p2_ms_at_change, p1_time, p2_time);
+                    }
+                },
+            }
+        } else if hourglass {
+            p2_ms_at_change + charged
+        } else {
+            p2_ms_at_change
+        };
+        let new_p2_time = TimeSetting::from_millis(new_p2_ms);
+        let new_delay = if control.mode.is_delay() && !in_overtime {
+            remaining_delay(time_since_change, increment)
+        } else {
+            None
+        };
+
+        // Update the buzzer
+        if remaining_buzzer_duration == 1 {
+            buzzer_pin
+                .set_off()
+                .map_err(|_| RuntimeError::PinWriteError)?;
+        }
+        remaining_buzzer_duration = remaining_buzzer_duration.saturating_sub(1);
+        // Don't beep while a simple delay is holding the clock still
+        if charged != 0
+            && match *turn {
+                Turn::P1 => new_p1_ms <= 1000 * 10 && new_p1_ms % 1000 == 0,
+                Turn::P2 => new_p2_ms <= 1000 * 10 && new_p2_ms % 1000 == 0,
+            }
+        {
+            buzzer_pin
+                .set_on()
+                .map_err(|_| RuntimeError::PinWriteError)?;
+            remaining_buzzer_duration = BUZZER_LENGTH;
+        }
+
+        // Lazy render
+        let tenths = display.shows_tenths();
+        if *turn != last_turn
+            // Only what's shown matters
+            || shown(&new_p1_time, tenths) != shown(&last_p1_time, tenths)
+            || shown(&new_p2_time, tenths) != shown(&last_p2_time, tenths)
+            || *phases != last_phases
+            || new_delay != last_delay
+            || stage_note != last_stage_note
+        {
+            last_turn = turn.clone();
+            if last_moves != Some(*moves) {
+                display
+                    .draw_details(moves, control.increments(moves), &control.mode)
+                    .map_err(|_| RuntimeError::DisplayError)?;
+                last_moves = Some(*moves);
+            }
+            display
+                .draw_countdown(
+                    turn,
+                    new_delay,
+                    stage_note,
+                    &new_p1_time,
+                    &new_p2_time,
+                    phases,
+                )
+                .map_err(|_| RuntimeError::DisplayError)?;
+            if *phases != last_phases {
+                // So anyone following along sees the overtime as it goes
+                serial_handler.send_phases(phases);
+            }
+            last_p1_time = new_p1_time;
+            last_p2_time = new_p2_time;
+            last_phases = *phases;
+            last_delay = new_delay;
+            last_stage_note = stage_note;
+        } else {
+            time.delay_ms(LOOP_DELAY);
+        }
+
+        if serial_handler.keep_alive(time) {
+            serial_handler.send_game(
+                control,
+                (new_p1_ms, new_p2_ms),
+                moves,
+                phases,
+                GameState::Running(turn.clone()),
+            );
+        }
+
+        // What the other side wants done, if it gets a say
+        let command = serial_handler.read_command();
+        // When it runs the game, the players' buttons don't, and its times are
+        // taken as given
+        let slave = serial_handler.role() == Some(Role::Slave);
+
+        // Its Sync replaces both times whether it runs the game or not
+        if let Some(SerialMsg::Sync {
+            p1_time: p1_ms,
+            p2_time: p2_ms,
+        }) = command
+        {
+            // Carry on with the turn, so what it's been charged so far stays
+            // charged
+            match *turn {
+                Turn::P1 => {
+                    p1_ms_at_change = p1_ms + charged;
+                    p2_ms_at_change = if hourglass {
+                        p2_ms.saturating_sub(charged)
+                    } else {
+                        p2_ms
+                    };
+                }
+                Turn::P2 => {
+                    p2_ms_at_change = p2_ms + charged;
+                    p1_ms_at_change = if hourglass {
+                        p1_ms.saturating_sub(charged)
+                    } else {
+                        p1_ms
+                    };
+                }
+            }
+            serial_handler.write(SerialMsg::Sync {
+                p1_time: p1_ms,
+                p2_time: p2_ms,
+            });
+            continue;
+        }
+
+        // START still pauses for a slave, so the game can always be stopped
+        if start.update(
+            start_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Falling)
+            || matches!(command, Some(SerialMsg::Pause { time: _ }))
+        {
+            // Unsafe subtraction since it's already been checked in the rendering code
+            match *turn {
+                Turn::P1 => {
+                    p1_ms_at_change -= charged;
+                    p2_ms_at_change = new_p2_ms;
+                }
+                Turn::P2 => {
+                    p2_ms_at_change -= charged;
+                    p1_ms_at_change = new_p1_ms;
+                }
+            }
+            if let (true, Some(SerialMsg::Pause { time: ms })) = (slave, &command) {
+                match *turn {
+                    Turn::P1 => p1_ms_at_change = *ms,
+                    Turn::P2 => p2_ms_at_change = *ms,
+                }
+            }
+            // Start button released; pause the game
+            break finish_countdown(p1_ms_at_change, p2_ms_at_change, p1_time, p2_time);
+        }
+        let p1_moved = (down.update(
+            down_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Rising)
+            && !slave)
+            || matches!(
+                command,
+                Some(SerialMsg::StartP2 {
+                    p1_time: _,
+                    move_number: _,
+                })
+            );
+        if p1_moved && *turn == Turn::P1 {
+            // Down/P1 press (switch to P2)
+            // Unsafe subtraction since it's already been checked in the rendering code
+            p1_ms_at_change = if in_overtime {
+                phases
+                    .0
+                    .after_move(&control.overtime, p1_ms_at_change - charged)
+            } else {
+                p1_ms_at_change - charged + move_bonus(time_since_change, control.mode, increment)
+            };
+            if hourglass {
+                // P2 gained what P1 used
+                p2_ms_at_change = new_p2_ms;
+            }
+            moves.0 += 1;
+            stage_note = if in_overtime {
+                None
+            } else {
+                control.stage_reached(moves.0)
+            };
+            if let Some(stage) = stage_note {
+                p1_ms_at_change += control.stages[stage - 1].time.into_millis();
+                serial_handler.write(SerialMsg::StageP1 {
+                    stage: stage as u32 + 1,
+                });
+            }
+            if let (
+                true,
+                Some(SerialMsg::StartP2 {
+                    p1_time: ms,
+                    move_number,
+                }),
+            ) = (slave, &command)
+            {
+                p1_ms_at_change = *ms;
+                moves.1 = move_number.saturating_sub(1) as u16;
+            }
+            serial_handler.write(SerialMsg::StartP2 {
+                p1_time: p1_ms_at_change, // TODO: fix this
+                move_number: moves.1 as u32 + 1,
+            });
+            if hourglass {
+                // P2's time changed too
+                serial_handler.write(SerialMsg::Sync {
+                    p1_time: p1_ms_at_change,
+                    p2_time: p2_ms_at_change,
+                });
+            }
+            last_change_time = time.millis();
+            *turn = Turn::P2
+        }
+        let p2_moved = (up.update(
+            up_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Rising)
+            && !slave)
+            || matches!(
+                command,
+                Some(SerialMsg::StartP1 {
+                    p2_time: _,
+                    move_number: _,
+                })
+            );
+        if p2_moved && *turn == Turn::P2 {
+            // Up/P2 press (switch to P1)
+            // Unsafe subtraction since it's already been checked in the rendering code
+            p2_ms_at_change = if in_overtime {
+                phases
+                    .1
+                    .after_move(&control.overtime, p2_ms_at_change - charged)
+            } else {
+                p2_ms_at_change - charged + move_bonus(time_since_change, control.mode, increment)
+            };
+            if hourglass {
+                // P1 gained what P2 used
+                p1_ms_at_change = new_p1_ms;
+            }
+            moves.1 += 1;
+            stage_note = if in_overtime {
+                None
+            } else {
+                control.stage_reached(moves.1)
+            };
+            if let Some(stage) = stage_note {
+                p2_ms_at_change += control.stages[stage - 1].time.into_millis();
+                serial_handler.write(SerialMsg::StageP2 {
+                    stage: stage as u32 + 1,
+                });
+            }
+            if let (
+                true,
+                Some(SerialMsg::StartP1 {
+                    p2_time: ms,
+                    move_number,
+                }),
+            ) = (slave, &command)
+            {
+                p2_ms_at_change = *ms;
+                moves.0 = move_number.saturating_sub(1) as u16;
+            }
+            serial_handler.write(SerialMsg::StartP1 {
+                p2_time: p2_ms_at_change, // TODO: fix this
+                move_number: moves.0 as u32 + 1,
+            });
+            if hourglass {
+                // P1's time changed too
+                serial_handler.write(SerialMsg::Sync {
+                    p1_time: p1_ms_at_change,
+                    p2_time: p2_ms_at_change,
+                });
+            }
+            last_change_time = time.millis();
+            *turn = Turn::P1;
+        }
+    })
+}
+
+/// The time taken off the running player's clock `elapsed` ms into their turn.
+fn charged_time(elapsed: u32, mode: TimeControlMode, increment: &Increment) -> u32 {
+    match mode {
+        TimeControlMode::SimpleDelay => elapsed.saturating_sub(increment.into_millis()),
+        TimeControlMode::Fischer | TimeControlMode::Bronstein | TimeControlMode::Hourglass => {
+            elapsed
+        }
+    }
+}
+
+/// The time given back to a player after a move which took `elapsed` ms.
+fn move_bonus(elapsed: u32, mode: TimeControlMode, increment: &Increment) -> u32 {
+    match mode {
+        TimeControlMode::Fischer => increment.into_millis(),
+        TimeControlMode::Bronstein => elapsed.min(increment.into_millis()),
+        TimeControlMode::SimpleDelay | TimeControlMode::Hourglass => 0,
+    }
+}
+
+/// The whole seconds of delay left `elapsed` ms into a turn, or `None` if it
+/// has run out.
+fn remaining_delay(elapsed: u32, increment: &Increment) -> Option<u8> {
+    match increment.into_millis().checked_sub(elapsed) {
+        Some(0) | None => None,
+        // Round up so the delay doesn't show 0 while it's still running
+        Some(x) => Some(((x + 999) / 1000) as u8),
+    }
+}
+
+fn finish_countdown(
+    p1_ms: u32,
+    p2_ms: u32,
+    p1_time: &mut TimeSetting,
+    p2_time: &mut TimeSetting,
+) -> CountdownResult {
+    *p1_time = TimeSetting::from_millis(p1_ms);
+    *p2_time = TimeSetting::from_millis(p2_ms);
+    match (p1_ms, p2_ms) {
+        (0, _) => CountdownResult::FinishedP1,
+        (_, 0) => CountdownResult::FinishedP2,
+        _ => CountdownResult::Paused,
+    }
+}
+
+/// What a display shows of `time`, in tenths of a second if it shows them
+/// under ten seconds and whole seconds otherwise.
+fn shown(time: &TimeSetting, tenths: bool) -> u32 {
+    match time.into_tenths() {
+        tenths_left if tenths && tenths_left < 10 * 10 => tenths_left,
+        _ => time.into_secs() * 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_modes_charge_and_refund() {
+        let increment = Increment::new(5);
+        assert_eq!(
+            charged_time(3000, TimeControlMode::SimpleDelay, &increment),
+            0
+        );
+        assert_eq!(
+            charged_time(7000, TimeControlMode::SimpleDelay, &increment),
+            2000
+        );
+        assert_eq!(
+            charged_time(7000, TimeControlMode::Bronstein, &increment),
+            7000
+        );
+        assert_eq!(
+            move_bonus(3000, TimeControlMode::Bronstein, &increment),
+            3000
+        );
+        assert_eq!(
+            move_bonus(7000, TimeControlMode::Bronstein, &increment),
+            5000
+        );
+        assert_eq!(move_bonus(7000, TimeControlMode::Fischer, &increment), 5000);
+        assert_eq!(move_bonus(7000, TimeControlMode::Hourglass, &increment), 0);
+        assert_eq!(remaining_delay(4001, &increment), Some(1));
+        assert_eq!(remaining_delay(5000, &increment), None);
+    }
+
+    #[test]
+    fn stages_follow_moves() {
+        let mut control = TimeControl::new();
+        control.increments = (Increment::new(2), Increment::new(3));
+        control.stages[0] = Stage {
+            after_moves: 40,
+            time: TimeSetting::new(30 * 60),
+            increment: Increment::new(30),
+        };
+        control.stage_count = 1;
+        assert_eq!(control.stage(39), 0);
+        assert_eq!(control.stage(40), 1);
+        assert_eq!(control.stage_reached(40), Some(1));
+        assert_eq!(control.stage_reached(41), None);
+        assert_eq!(control.increment(&Turn::P2, 39), &Increment::new(3));
+        assert_eq!(control.increment(&Turn::P2, 40), &Increment::new(30));
+    }
+
+    #[test]
+    fn byo_yomi_periods_run_out() {
+        let overtime = Overtime::new(OvertimeMode::ByoYomi);
+        let mut phase = Phase::Main;
+        assert_eq!(phase.next_period(&overtime), Some(30_000));
+        assert_eq!(phase, Phase::ByoYomi { periods: 5 });
+        assert_eq!(phase.after_move(&overtime, 12_000), 30_000);
+        for periods in (1..5).rev() {
+            assert_eq!(phase.next_period(&overtime), Some(30_000));
+            assert_eq!(phase, Phase::ByoYomi { periods });
+        }
+        assert_eq!(phase.next_period(&overtime), None);
+    }
+
+    #[test]
+    fn canadian_blocks_refill() {
+        let overtime = Overtime {
+            mode: OvertimeMode::Canadian,
+            count: 2,
+            period: TimeSetting::new(60),
+        };
+        let mut phase = Phase::Main;
+        assert_eq!(phase.next_period(&overtime), Some(60_000));
+        assert_eq!(phase.after_move(&overtime, 40_000), 40_000);
+        assert_eq!(phase, Phase::Canadian { moves: 1 });
+        assert_eq!(phase.after_move(&overtime, 10_000), 60_000);
+        assert_eq!(phase, Phase::Canadian { moves: 2 });
+        assert_eq!(phase.next_period(&overtime), None);
+    }
+}
@@ -0,0 +1,184 @@
+//! What the screens show, kept apart from how it's drawn, so the same screens
+//! can drive any kind of display.
+//!
+//! The screens only say what should be on the display through [`ClockDisplay`],
+//! and each kind of display decides where and how to draw it. [`CharLcd`] draws
+//! on a character LCD like the HD44780, of whichever [`Geometry`], and [`Oled`]
+//! on a 128x64 OLED like the SSD1306. [`SegmentClocks`] shows each player's
+//! clock on a seven-segment display facing them.
+
+use crate::{
+    countdown::{Overtime, Phase, Stage, TimeControlMode, Turn},
+    time_set::{Increment, TimeSetPart, TimeSetting},
+};
+
+pub use char_lcd::{CharLcd, Geometry};
+pub use oled::Oled;
+pub use segments::{NoStatus, SegmentClocks, SegmentsError};
+
+mod char_lcd;
+mod font;
+mod oled;
+mod segments;
+
+/// How connecting to the website went, shown under the splash screen.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Connection {
+    Connecting,
+    Connected,
+    NotConnected,
+}
+
+/// The ways a display can show the countdown, picked by holding START while the
+/// clock starts up.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Layout {
+    /// Both clocks side by side
+    Standard,
+    /// Only the running player's clock, as large as the display allows
+    BigDigits,
+}
+
+impl Layout {
+    pub fn next(&self) -> Layout {
+        match self {
+            Layout::Standard => Layout::BigDigits,
+            Layout::BigDigits => Layout::Standard,
+        }
+    }
+}
+
+/// The setting being changed on one of the set-up screens, along with
+/// everything else on that screen.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Setting<'a> {
+    /// The entry at this index in the preset menu
+    Preset(usize),
+    Times(&'a TimeSetting, &'a TimeSetting),
+    Mode(&'a TimeControlMode),
+    Increments(&'a Increment, &'a Increment, &'a TimeControlMode),
+    /// The stage at `index` in the list of stages after the first
+    Stage {
+        index: usize,
+        stage: &'a Stage,
+        mode: &'a TimeControlMode,
+    },
+    /// `part` picks whether the kind of overtime or its settings are being
+    /// changed.
+    Overtime {
+        part: TimeSetPart,
+        overtime: &'a Overtime,
+    },
+    /// The user preset to save to, or 0 for not saving
+    SavePreset(usize),
+}
+
+/// A message shown over the clocks while they're stopped.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Banner<'a> {
+    /// Before the game: press P1 or P2 to begin
+    Begin,
+    /// Before the game: press START to go back to the set-up screens
+    Cancel,
+    /// Before the game: which side is which
+    Players,
+    Paused,
+    /// Paused: press START to go back to the set-up screens
+    Restart,
+    /// Paused: press P1 or P2 to carry on
+    Resume,
+    /// The number of moves each player has made
+    Moves(&'a (u16, u16)),
+    /// The game is over, with this player out of time
+    TimeUp(Turn),
+}
+
+/// Something the clock can show the game on.
+///
+/// Every method draws one part of a screen and leaves the rest as it was, so
+/// the screens only redraw what changed. While the clocks are stopped,
+/// [`draw_banner`] and [`draw_times`] make up the screen between them.
+///
+/// [`draw_banner`]: ClockDisplay::draw_banner
+/// [`draw_times`]: ClockDisplay::draw_times
+pub trait ClockDisplay {
+    type Error;
+
+    /// Switches to `layout` for the countdown. Displays with only one way of
+    /// showing it can leave this as it is.
+    fn set_layout(&mut self, _layout: Layout) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Whether the display shows tenths of a second once a clock is under ten
+    /// seconds, so the countdown redraws it ten times as often then.
+    fn shows_tenths(&self) -> bool {
+        false
+    }
+
+    /// Sets the times both players start the game with, for displays which
+    /// show how much of it is left.
+    fn set_start_times(
+        &mut self,
+        _p1_time: &TimeSetting,
+        _p2_time: &TimeSetting,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Clears the display and shows the name and version of the clock.
+    fn draw_splash(&mut self, version: &str) -> Result<(), Self::Error>;
+
+    /// Shows how connecting to the website is going, under the splash screen.
+    fn draw_connection(&mut self, connection: Connection) -> Result<(), Self::Error>;
+
+    /// Shows the layout which was just picked, under the splash screen.
+    fn draw_layout(&mut self, layout: Layout) -> Result<(), Self::Error>;
+
+    /// Shows a whole set-up screen. `blink_off_part` is the part of the setting
+    /// to leave out while it blinks, if any.
+    fn draw_setting(
+        &mut self,
+        setting: &Setting,
+        blink_off_part: Option<TimeSetPart>,
+    ) -> Result<(), Self::Error>;
+
+    /// Shows the whole screen while a clock is running: whose it is, any delay
+    /// left in their turn, the stage the other player just got to and the
+    /// times.
+    fn draw_countdown(
+        &mut self,
+        turn: &Turn,
+        delay_secs: Option<u8>,
+        stage_note: Option<usize>,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), Self::Error>;
+
+    /// Shows both players' clocks while they're stopped, in whichever part of
+    /// the time control they're in.
+    fn draw_times(
+        &mut self,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), Self::Error>;
+
+    /// Shows the number of moves each player has made and the increment or
+    /// delay they'll get, for displays with room for it next to the clocks.
+    fn draw_details(
+        &mut self,
+        _moves: &(u16, u16),
+        _increments: (&Increment, &Increment),
+        _mode: &TimeControlMode,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Shows a message along with the times while the clocks are stopped.
+    fn draw_banner(&mut self, banner: &Banner) -> Result<(), Self::Error>;
+
+    /// Clears the display and shows an error which stopped the clock.
+    fn draw_error(&mut self, name: &str) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,895 @@
+//! The screens on a 16x2 character LCD, as the clock has always shown them,
+//! and a countdown in digits two lines tall made of custom characters.
+
+use ufmt::uwrite;
+
+use crate::{
+    countdown::{Overtime, OvertimeMode, Phase, Stage, TimeControlMode, Turn},
+    display::{Banner, ClockDisplay, Connection, Layout, Setting},
+    hal::Lcd,
+    presets::preset,
+    storage::{LAST_USED_SLOT, SLOT_COUNT},
+    time_set::{Increment, TimeSetPart, TimeSetting},
+    LCD_LINE_LENGTH,
+};
+
+/// The number of characters on each line of the screens, which are centred on
+/// wider displays.
+const COLUMNS: usize = 16;
+
+// The custom characters the big digits are made of
+/// A bar along the top
+const BAR_TOP: u8 = 0;
+/// A bar along the bottom
+const BAR_BOTTOM: u8 = 1;
+/// Bars along the top and bottom
+const BARS: u8 = 2;
+const BLOCK: u8 = 3;
+/// Half of a colon
+const DOT: u8 = 4;
+const ARROW_LEFT: u8 = 5;
+const ARROW_RIGHT: u8 = 6;
+
+/// The bitmaps of the custom characters, in order.
+const GLYPHS: [[u8; 8]; 7] = [
+    [0x1f, 0x1f, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0x1f, 0x1f],
+    [0x1f, 0x1f, 0, 0, 0, 0, 0x1f, 0x1f],
+    [0x1f; 8],
+    [0, 0, 0, 0b01110, 0b01110, 0, 0, 0],
+    [
+        0b00010, 0b00110, 0b01110, 0b11110, 0b01110, 0b00110, 0b00010, 0,
+    ],
+    [
+        0b01000, 0b01100, 0b01110, 0b01111, 0b01110, 0b01100, 0b01000, 0,
+    ],
+];
+
+/// The top and bottom halves of each big digit, three characters wide.
+const BIG_DIGITS: [[[u8; 3]; 2]; 10] = [
+    [[BLOCK, BAR_TOP, BLOCK], [BLOCK, BAR_BOTTOM, BLOCK]],
+    [[BAR_TOP, BLOCK, b' '], [BAR_BOTTOM, BLOCK, BAR_BOTTOM]],
+    [[BARS, BARS, BLOCK], [BLOCK, BAR_BOTTOM, BAR_BOTTOM]],
+    [[BARS, BARS, BLOCK], [BAR_BOTTOM, BAR_BOTTOM, BLOCK]],
+    [[BLOCK, BAR_BOTTOM, BLOCK], [b' ', b' ', BLOCK]],
+    [[BLOCK, BARS, BARS], [BAR_BOTTOM, BAR_BOTTOM, BLOCK]],
+    [[BLOCK, BARS, BARS], [BLOCK, BAR_BOTTOM, BLOCK]],
+    [[BAR_TOP, BAR_TOP, BLOCK], [b' ', b' ', BLOCK]],
+    [[BLOCK, BARS, BLOCK], [BLOCK, BAR_BOTTOM, BLOCK]],
+    [[BLOCK, BARS, BLOCK], [BAR_BOTTOM, BAR_BOTTOM, BLOCK]],
+];
+
+// The custom characters the narrow digits are made of, when both clocks are
+// shown. Each is some of the edges of a character, like the segments of a
+// seven-segment digit, and the middle bar is along whichever half lets all ten
+// digits be made of eight characters.
+/// The top and both sides
+const CAP: u8 = 0;
+/// Both sides and the bottom
+const CUP: u8 = 1;
+/// The right side
+const SIDE: u8 = 2;
+/// The top and the right side
+const HOOK: u8 = 3;
+/// Every edge
+const BOX: u8 = 4;
+/// Every edge but the left
+const OPEN_LEFT: u8 = 5;
+/// Every edge but the right
+const OPEN_RIGHT: u8 = 6;
+/// The right side and the bottom
+const CORNER: u8 = 7;
+
+/// The bitmaps of the narrow digits' custom characters, in order.
+const NARROW_GLYPHS: [[u8; 8]; 8] = [
+    [0x1f, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11],
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1f],
+    [0x01; 8],
+    [0x1f, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01],
+    [0x1f, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1f],
+    [0x1f, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x1f],
+    [0x1f, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+    [0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x1f],
+];
+
+/// The top and bottom halves of each narrow digit, one character wide.
+const NARROW_DIGITS: [[u8; 2]; 10] = [
+    [CAP, CUP],
+    [SIDE, SIDE],
+    [HOOK, OPEN_RIGHT],
+    [HOOK, OPEN_LEFT],
+    [CUP, SIDE],
+    [OPEN_RIGHT, CORNER],
+    [OPEN_RIGHT, CUP],
+    [HOOK, SIDE],
+    [CAP, BOX],
+    [CAP, OPEN_LEFT],
+];
+
+// The narrow digits need every custom character, so the arrows and colon
+// beside them come from the HD44780's own characters
+const ROM_ARROW_RIGHT: u8 = 0x7e;
+const ROM_ARROW_LEFT: u8 = 0x7f;
+
+/// Which custom characters are loaded. Between them the two ways of showing
+/// big digits need more than the eight the HD44780 has.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Glyphs {
+    /// Three characters wide, with only the running player's clock
+    Wide,
+    /// One character wide, with both clocks
+    Narrow,
+}
+
+impl Glyphs {
+    fn bitmaps(self) -> &'static [[u8; 8]] {
+        match self {
+            Glyphs::Wide => &GLYPHS,
+            Glyphs::Narrow => &NARROW_GLYPHS,
+        }
+    }
+}
+
+/// The size of a character LCD. Anything from 16x2 works, and the rows after
+/// the second show the moves and increments along with the clocks.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Geometry {
+    pub columns: u8,
+    pub rows: u8,
+}
+
+impl Geometry {
+    pub const LCD_16X2: Geometry = Geometry {
+        columns: 16,
+        rows: 2,
+    };
+    pub const LCD_20X4: Geometry = Geometry {
+        columns: 20,
+        rows: 4,
+    };
+
+    /// Where `column` of `row` is in the display's memory. Like on the
+    /// HD44780, the third and fourth rows carry on from the ends of the first
+    /// and second.
+    pub fn position(&self, row: u8, column: u8) -> u8 {
+        (row % 2) * LCD_LINE_LENGTH + (row / 2) * self.columns + column
+    }
+}
+
+/// Draws the screens on a character LCD, 16 characters to a line.
+#[derive(Debug)]
+pub struct CharLcd<L: Lcd> {
+    pub lcd: L,
+    geometry: Geometry,
+    layout: Layout,
+    /// The custom characters loaded, if any
+    glyphs: Option<Glyphs>,
+}
+
+impl<L: Lcd> CharLcd<L> {
+    /// Starts out with the standard layout on a 16x2 display.
+    pub fn new(lcd: L) -> Self {
+        CharLcd::with_geometry(lcd, Geometry::LCD_16X2)
+    }
+
+    pub fn with_geometry(lcd: L, geometry: Geometry) -> Self {
+        CharLcd {
+            lcd,
+            geometry,
+            layout: Layout::Standard,
+            glyphs: None,
+        }
+    }
+
+    /// Loads `glyphs` into the custom characters, unless they're there
+    /// already.
+    fn load_glyphs(&mut self, glyphs: Glyphs) -> Result<(), L::Error> {
+        if self.glyphs != Some(glyphs) {
+            for (index, rows) in glyphs.bitmaps().iter().enumerate() {
+                self.lcd.define_char(index as u8, rows)?;
+            }
+            self.glyphs = Some(glyphs);
+        }
+        Ok(())
+    }
+}
+
+impl<L: Lcd> ClockDisplay for CharLcd<L> {
+    type Error = L::Error;
+
+    fn set_layout(&mut self, layout: Layout) -> Result<(), L::Error> {
+        // The custom characters are loaded when they're first needed
+        self.layout = layout;
+        Ok(())
+    }
+
+    fn draw_splash(&mut self, version: &str) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        lcd.clear()?;
+        start_row(0, geometry, lcd)?;
+        uwrite!(lcd, " OpenChessClock ")?;
+        start_row(1, geometry, lcd)?;
+        uwrite!(lcd, "     v{}     ", version)
+    }
+
+    fn draw_connection(&mut self, connection: Connection) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        start_row(1, geometry, lcd)?;
+        uwrite!(
+            lcd,
+            "{}",
+            match connection {
+                Connection::Connecting => "  Connecting... ",
+                Connection::Connected => "   Connected.   ",
+                Connection::NotConnected => " No connection. ",
+            }
+        )
+    }
+
+    fn draw_layout(&mut self, layout: Layout) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        start_row(1, geometry, lcd)?;
+        uwrite!(
+            lcd,
+            "{}",
+            match layout {
+                Layout::Standard => "Layout: standard",
+                Layout::BigDigits => "Layout: big     ",
+            }
+        )
+    }
+
+    fn draw_setting(
+        &mut self,
+        setting: &Setting,
+        blink_off_part: Option<TimeSetPart>,
+    ) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        match *setting {
+            Setting::Preset(index) => {
+                render_title("     Preset     ", geometry, lcd)?;
+                render_preset(index, blink_off_part, lcd)
+            }
+            Setting::Times(p1_time, p2_time) => {
+                render_title("P1  Set time  P2", geometry, lcd)?;
+                render_time(p1_time, p2_time, blink_off_part, lcd)
+            }
+            Setting::Mode(mode) => {
+                render_title("  Time control  ", geometry, lcd)?;
+                render_mode(mode, blink_off_part, lcd)
+            }
+            Setting::Increments(p1_increment, p2_increment, mode) => {
+                render_title(
+                    if mode.is_delay() {
+                        "P1    Delay   P2"
+                    } else {
+                        "P1 Increment  P2"
+                    },
+                    geometry,
+                    lcd,
+                )?;
+                render_increment(p1_increment, p2_increment, mode, blink_off_part, lcd)
+            }
+            Setting::Stage { index, stage, mode } => {
+                render_stage(index, stage, mode, blink_off_part, geometry, lcd)
+            }
+            Setting::Overtime { part, overtime } => {
+                render_overtime(part, overtime, blink_off_part, geometry, lcd)
+            }
+            Setting::SavePreset(slot) => render_save_preset(slot, blink_off_part, geometry, lcd),
+        }?;
+        if geometry.rows >= 4 {
+            start_row(2, geometry, lcd)?;
+            uwrite!(lcd, "                ")?;
+            start_row(3, geometry, lcd)?;
+            uwrite!(lcd, "UP/DN  START: ok")?;
+        }
+        Ok(())
+    }
+
+    fn draw_countdown(
+        &mut self,
+        turn: &Turn,
+        delay_secs: Option<u8>,
+        stage_note: Option<usize>,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        let (time, phase) = match turn {
+            Turn::P1 => (p1_time, &phases.0),
+            Turn::P2 => (p2_time, &phases.1),
+        };
+        // Overtime doesn't fit in big digits, so it's shown as usual
+        if self.layout == Layout::Standard || *phase != Phase::Main {
+            start_row(0, geometry, lcd)?;
+            render_status(turn, delay_secs, stage_note, lcd)?;
+            start_row(1, geometry, lcd)?;
+            return render_clocks(p1_time, p2_time, phases, lcd);
+        }
+
+        // Both clocks fit in narrow digits when neither has an hour left
+        let lines = if [(p1_time, &phases.0), (p2_time, &phases.1)]
+            .iter()
+            .all(|(time, phase)| **phase == Phase::Main && time.into_secs() < 60 * 60)
+        {
+            self.load_glyphs(Glyphs::Narrow)?;
+            render_narrow_clocks(turn, delay_secs, stage_note, p1_time, p2_time)
+        } else {
+            self.load_glyphs(Glyphs::Wide)?;
+            render_big_clock(turn, delay_secs, stage_note, time)
+        };
+
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        start_row(0, geometry, lcd)?;
+        render_line(&lines[0], lcd)?;
+        start_row(1, geometry, lcd)?;
+        render_line(&lines[1], lcd)
+    }
+
+    fn draw_times(
+        &mut self,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        start_row(1, geometry, lcd)?;
+        render_clocks(p1_time, p2_time, phases, lcd)
+    }
+
+    fn draw_details(
+        &mut self,
+        moves: &(u16, u16),
+        increments: (&Increment, &Increment),
+        mode: &TimeControlMode,
+    ) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        if geometry.rows < 4 {
+            return Ok(());
+        }
+        start_row(2, geometry, lcd)?;
+        render_moves(moves, lcd)?;
+        start_row(3, geometry, lcd)?;
+        if *mode == TimeControlMode::Hourglass {
+            render_mode(mode, None, lcd)
+        } else {
+            render_increment(increments.0, increments.1, mode, None, lcd)
+        }
+    }
+
+    fn draw_banner(&mut self, banner: &Banner) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        start_row(0, geometry, lcd)?;
+        let text = match *banner {
+            Banner::Begin => " P1/P2 to begin ",
+            Banner::Cancel => "START to cancel ",
+            Banner::Players => " P1          P2 ",
+            Banner::Paused => " P1  Paused  P2 ",
+            Banner::Restart => "START to restart",
+            Banner::Resume => "P1/P2 to resume ",
+            Banner::Moves(moves) => return render_moves(moves, lcd),
+            Banner::TimeUp(Turn::P1) => "[P1]  Time's up!",
+            Banner::TimeUp(Turn::P2) => "Time's up!  [P2]",
+        };
+        uwrite!(lcd, "{}", text)
+    }
+
+    fn draw_error(&mut self, name: &str) -> Result<(), L::Error> {
+        let (lcd, geometry) = (&mut self.lcd, &self.geometry);
+        lcd.clear()?;
+        start_row(0, geometry, lcd)?;
+        uwrite!(lcd, "fatal error")?;
+        start_row(1, geometry, lcd)?;
+        uwrite!(lcd, "{}", name)
+    }
+}
+
+/// Moves the cursor to the start of `row` of the screen.
+fn start_row<L: Lcd>(row: u8, geometry: &Geometry, lcd: &mut L) -> Result<(), L::Error> {
+    lcd.set_cursor_pos(geometry.position(row, (geometry.columns - COLUMNS as u8) / 2))
+}
+
+/// Renders the heading of a set-up screen, leaving the cursor at the start of
+/// the second line for the setting.
+fn render_title<L: Lcd>(title: &str, geometry: &Geometry, lcd: &mut L) -> Result<(), L::Error> {
+    start_row(0, geometry, lcd)?;
+    uwrite!(lcd, "{}", title)?;
+    start_row(1, geometry, lcd)
+}
+
+/// Renders the top line of the countdown: whose clock is running, any delay
+/// left in their turn and the stage the other player just got to.
+pub(super) fn render_status<L: Lcd>(
+    turn: &Turn,
+    delay_secs: Option<u8>,
+    stage_note: Option<usize>,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    if *turn == Turn::P1 {
+        uwrite!(lcd, "[P1]")?;
+        match delay_secs {
+            Some(secs) if secs > 9 => uwrite!(lcd, "d{}", secs)?,
+            Some(secs) => uwrite!(lcd, "d{} ", secs)?,
+            None => uwrite!(lcd, "   ")?,
+        }
+        match stage_note {
+            Some(stage) => uwrite!(lcd, "<< P2 St{}", stage + 1),
+            None => uwrite!(lcd, "<<    P2 "),
+        }
+    } else {
+        match stage_note {
+            Some(stage) => uwrite!(lcd, "P1 St{} >>", stage + 1)?,
+            None => uwrite!(lcd, " P1    >>")?,
+        }
+        match delay_secs {
+            Some(secs) if secs > 9 => uwrite!(lcd, "d{}", secs)?,
+            Some(secs) => uwrite!(lcd, " d{}", secs)?,
+            None => uwrite!(lcd, "   ")?,
+        }
+        uwrite!(lcd, "[P2]")
+    }
+}
+
+/// Renders both players' clocks, showing whichever part of the time control
+/// they're in.
+fn render_clocks<L: Lcd>(
+    p1_time: &TimeSetting,
+    p2_time: &TimeSetting,
+    phases: &(Phase, Phase),
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    render_clock(p1_time, &phases.0, lcd)?;
+    uwrite!(lcd, "  ")?;
+    render_clock(p2_time, &phases.1, lcd)
+}
+
+pub(super) fn render_clock<L: Lcd>(
+    time: &TimeSetting,
+    phase: &Phase,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    match *phase {
+        Phase::Main => render_time_part(time, false, false, lcd),
+        Phase::ByoYomi { periods } => {
+            // e.g. `BY 3x30` for 3 periods left with 30s left in this one
+            let secs = time.into_secs().min(99);
+            if periods > 9 {
+                uwrite!(lcd, "BY{}x", periods)?;
+            } else {
+                uwrite!(lcd, "BY {}x", periods)?;
+            }
+            if secs > 9 {
+                uwrite!(lcd, "{}", secs)
+            } else {
+                uwrite!(lcd, "0{}", secs)
+            }
+        }
+        Phase::Canadian { moves } => {
+            // e.g. `12/4:59` for 12 moves left to make in 4:59, or `12/ 15m`
+            // when the block has ten minutes or more left
+            if moves > 9 {
+                uwrite!(lcd, "{}/", moves)?;
+            } else {
+                uwrite!(lcd, " {}/", moves)?;
+            }
+            let secs = time.into_secs();
+            if secs >= 10 * 60 {
+                uwrite!(lcd, " {}m", (secs / 60).min(99))
+            } else if secs % 60 > 9 {
+                uwrite!(lcd, "{}:{}", secs / 60, secs % 60)
+            } else {
+                uwrite!(lcd, "{}:0{}", secs / 60, secs % 60)
+            }
+        }
+    }
+}
+
+/// Renders the number of moves each player has made, lined up with their times.
+pub(super) fn render_moves<L: Lcd>(moves: &(u16, u16), lcd: &mut L) -> Result<(), L::Error> {
+    let p1_moves = moves.0.min(999);
+    let p2_moves = moves.1.min(999);
+    if p1_moves < 10 {
+        uwrite!(lcd, "  ")?;
+    } else if p1_moves < 100 {
+        uwrite!(lcd, " ")?;
+    }
+    uwrite!(lcd, "{}   Moves  {}", p1_moves, p2_moves)?;
+    if p2_moves < 10 {
+        uwrite!(lcd, "  ")?;
+    } else if p2_moves < 100 {
+        uwrite!(lcd, " ")?;
+    }
+    Ok(())
+}
+
+/// Renders the running player's clock in big digits, with an arrow pointing at
+/// them from their side and anything else in the far corner.
+fn render_big_clock(
+    turn: &Turn,
+    delay_secs: Option<u8>,
+    stage_note: Option<usize>,
+    time: &TimeSetting,
+) -> [[u8; COLUMNS]; 2] {
+    let mut lines = [[b' '; COLUMNS]; 2];
+    let (time_at, notes_at) = match turn {
+        Turn::P1 => {
+            lines[0][0] = ARROW_LEFT;
+            (1, COLUMNS - 2)
+        }
+        Turn::P2 => {
+            lines[0][COLUMNS - 1] = ARROW_RIGHT;
+            (2, 0)
+        }
+    };
+    render_big_time(time, time_at, &mut lines);
+    render_notes(delay_secs, stage_note, notes_at, &mut lines);
+    lines
+}
+
+/// Renders both clocks in narrow digits, e.g. ` 5:07 ←d5  0:09 `, with an
+/// arrow pointing at the running player and anything else between them.
+fn render_narrow_clocks(
+    turn: &Turn,
+    delay_secs: Option<u8>,
+    stage_note: Option<usize>,
+    p1_time: &TimeSetting,
+    p2_time: &TimeSetting,
+) -> [[u8; COLUMNS]; 2] {
+    let mut lines = [[b' '; COLUMNS]; 2];
+    render_narrow_time(p1_time, 1, &mut lines);
+    render_narrow_time(p2_time, COLUMNS - 6, &mut lines);
+    match turn {
+        Turn::P1 => lines[0][6] = ROM_ARROW_LEFT,
+        Turn::P2 => lines[0][COLUMNS - 7] = ROM_ARROW_RIGHT,
+    }
+    render_notes(delay_secs, stage_note, 7, &mut lines);
+    lines
+}
+
+/// Renders the delay left in the running player's turn and the stage the other
+/// player just got to into two columns from `at`, e.g. `d5` over `S2`. Any
+/// delay from nine seconds up is shown as `d9`, as there's no room for more.
+fn render_notes(
+    delay_secs: Option<u8>,
+    stage_note: Option<usize>,
+    at: usize,
+    lines: &mut [[u8; COLUMNS]; 2],
+) {
+    if let Some(secs) = delay_secs {
+        lines[0][at..at + 2].copy_from_slice(&[b'd', b'0' + secs.min(9)]);
+    }
+    if let Some(stage) = stage_note {
+        // The first stage is the one set up on the earlier screens
+        lines[1][at..at + 2].copy_from_slice(&[b'S', b'0' + (stage + 1) as u8]);
+    }
+}
+
+/// Renders `time` into `lines` in big digits, 13 characters wide from
+/// `at`: `MM:SS` under an hour, or `H:MM` with the seconds small beside it.
+fn render_big_time(time: &TimeSetting, at: usize, lines: &mut [[u8; COLUMNS]; 2]) {
+    let (hrs, mins, secs) = time.into_hrs_mins_secs();
+    let colon_at = if hrs == 0 {
+        // No leading zero, like the standard layout's hours
+        if mins > 9 {
+            render_big_digit(mins / 10, at, lines);
+        }
+        render_big_digit(mins % 10, at + 3, lines);
+        at + 6
+    } else {
+        render_big_digit(hrs.min(9), at, lines);
+        at + 3
+    };
+    lines[0][colon_at] = DOT;
+    lines[1][colon_at] = DOT;
+    if hrs == 0 {
+        render_big_digit(secs / 10, colon_at + 1, lines);
+        render_big_digit(secs % 10, colon_at + 4, lines);
+    } else {
+        render_big_digit(mins / 10, colon_at + 1, lines);
+        render_big_digit(mins % 10, colon_at + 4, lines);
+        lines[1][colon_at + 8..colon_at + 10]
+            .copy_from_slice(&[b'0' + secs / 10, b'0' + secs % 10]);
+    }
+}
+
+fn render_big_digit(digit: u8, at: usize, lines: &mut [[u8; COLUMNS]; 2]) {
+    lines[0][at..at + 3].copy_from_slice(&BIG_DIGITS[digit as usize][0]);
+    lines[1][at..at + 3].copy_from_slice(&BIG_DIGITS[digit as usize][1]);
+}
+
+/// Renders `time`, which is under an hour, into `lines` in narrow digits as
+/// `MM:SS` from `at`.
+fn render_narrow_time(time: &TimeSetting, at: usize, lines: &mut [[u8; COLUMNS]; 2]) {
+    let (_, mins, secs) = time.into_hrs_mins_secs();
+    // No leading zero, like the standard layout's hours
+    if mins > 9 {
+        render_narrow_digit(mins / 10, at, lines);
+    }
+    render_narrow_digit(mins % 10, at + 1, lines);
+    // A dot low in the top half and one high in the bottom half
+    lines[0][at + 2] = b'.';
+    lines[1][at + 2] = b'\'';
+    render_narrow_digit(secs / 10, at + 3, lines);
+    render_narrow_digit(secs % 10, at + 4, lines);
+}
+
+fn render_narrow_digit(digit: u8, at: usize, lines: &mut [[u8; COLUMNS]; 2]) {
+    lines[0][at] = NARROW_DIGITS[digit as usize][0];
+    lines[1][at] = NARROW_DIGITS[digit as usize][1];
+}
+
+/// Writes a line built up a character at a time.
+fn render_line<L: Lcd>(line: &[u8], lcd: &mut L) -> Result<(), L::Error> {
+    // Only ASCII and custom characters, which are valid UTF-8 too, are used
+    lcd.write_str(core::str::from_utf8(line).unwrap_or_default())
+}
+
+fn render_time<L: Lcd>(
+    p1_time: &TimeSetting,
+    p2_time: &TimeSetting,
+    blink_off_part: Option<TimeSetPart>,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    render_time_part(
+        p1_time,
+        blink_off_part.is_some_and(|b| b == TimeSetPart::P1SetMin),
+        blink_off_part.is_some_and(|b| b == TimeSetPart::P1SetSec),
+        lcd,
+    )?;
+    uwrite!(lcd, "  ")?;
+    render_time_part(
+        p2_time,
+        blink_off_part.is_some_and(|b| b == TimeSetPart::P2SetMin),
+        blink_off_part.is_some_and(|b| b == TimeSetPart::P2SetSec),
+        lcd,
+    )
+}
+
+fn render_time_part<L: Lcd>(
+    time: &TimeSetting,
+    hide_min: bool,
+    hide_sec: bool,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    let parts = time.into_hrs_mins_secs();
+    if !hide_min {
+        // Hour
+        uwrite!(lcd, "{}:", parts.0.min(9))?;
+        // Minute
+        if parts.1 > 9 {
+            uwrite!(lcd, "{}:", parts.1)?;
+        } else {
+            uwrite!(lcd, "0{}:", parts.1)?;
+        }
+    } else {
+        uwrite!(lcd, " :  :")?;
+    }
+    if !hide_sec {
+        // Second
+        if parts.2 > 9 {
+            uwrite!(lcd, "{}", parts.2)?;
+        } else {
+            uwrite!(lcd, "0{}", parts.2)?;
+        }
+    } else {
+        uwrite!(lcd, "  ")?;
+    }
+    Ok(())
+}
+
+fn render_preset<L: Lcd>(
+    index: usize,
+    blink_off_part: Option<TimeSetPart>,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    if blink_off_part.is_some_and(|b| b == TimeSetPart::Preset) {
+        uwrite!(lcd, "                ")
+    } else if index == LAST_USED_SLOT {
+        uwrite!(lcd, "   Last used    ")
+    } else if index < SLOT_COUNT {
+        render_user_slot(index - LAST_USED_SLOT, lcd)
+    } else {
+        match preset(index - SLOT_COUNT) {
+            Some(preset) => uwrite!(lcd, "{}", preset.name),
+            None => uwrite!(lcd, "     Custom     "),
+        }
+    }
+}
+
+/// Renders both lines of the screen asking whether to save a time control set
+/// by hand as a user preset.
+fn render_save_preset<L: Lcd>(
+    slot: usize,
+    blink_off_part: Option<TimeSetPart>,
+    geometry: &Geometry,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    start_row(0, geometry, lcd)?;
+    uwrite!(lcd, "Save as preset? ")?;
+    start_row(1, geometry, lcd)?;
+    if blink_off_part.is_some_and(|b| b == TimeSetPart::SavePreset) {
+        uwrite!(lcd, "                ")
+    } else if slot == 0 {
+        uwrite!(lcd, "       No       ")
+    } else {
+        render_user_slot(slot, lcd)
+    }
+}
+
+fn render_user_slot<L: Lcd>(slot: usize, lcd: &mut L) -> Result<(), L::Error> {
+    uwrite!(lcd, "  User preset {} ", slot)
+}
+
+fn render_mode<L: Lcd>(
+    mode: &TimeControlMode,
+    blink_off_part: Option<TimeSetPart>,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    uwrite!(
+        lcd,
+        "{}",
+        if blink_off_part.is_some_and(|b| b == TimeSetPart::SetMode) {
+            "                "
+        } else {
+            match mode {
+                TimeControlMode::Fischer => "   Increment    ",
+                TimeControlMode::Bronstein => "Bronstein delay ",
+                TimeControlMode::SimpleDelay => "  Simple delay  ",
+                TimeControlMode::Hourglass => "   Hourglass    ",
+            }
+        }
+    )
+}
+
+fn render_increment<L: Lcd>(
+    p1_increment: &Increment,
+    p2_increment: &Increment,
+    mode: &TimeControlMode,
+    blink_off_part: Option<TimeSetPart>,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    // Line the increments up with the right edge of the times
+    uwrite!(lcd, "   ")?;
+    if !blink_off_part.is_some_and(|b| b == TimeSetPart::P1SetInc) {
+        render_increment_part(p1_increment, mode, lcd)?;
+    } else {
+        uwrite!(lcd, "    ")?;
+    }
+    uwrite!(lcd, "     ")?;
+    if !blink_off_part.is_some_and(|b| b == TimeSetPart::P2SetInc) {
+        render_increment_part(p2_increment, mode, lcd)?;
+    } else {
+        uwrite!(lcd, "    ")?;
+    }
+    Ok(())
+}
+
+fn render_increment_part<L: Lcd>(
+    increment: &Increment,
+    mode: &TimeControlMode,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    if increment.into_secs() <= 9 {
+        uwrite!(lcd, " ")?;
+    }
+    uwrite!(
+        lcd,
+        "{}{}s",
+        if mode.is_delay() { "d" } else { "+" },
+        increment.into_secs()
+    )
+}
+
+/// Renders both lines of the set-up screen for the stage at `index` in the
+/// list of stages after the first.
+fn render_stage<L: Lcd>(
+    index: usize,
+    stage: &Stage,
+    mode: &TimeControlMode,
+    blink_off_part: Option<TimeSetPart>,
+    geometry: &Geometry,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    start_row(0, geometry, lcd)?;
+    // The first stage is the one set up on the earlier screens
+    uwrite!(lcd, "Stage {} after ", index + 2)?;
+    if blink_off_part.is_some_and(|b| b == TimeSetPart::StageMoves) {
+        uwrite!(lcd, "  ")?;
+    } else if stage.after_moves == 0 {
+        uwrite!(lcd, "--")?;
+    } else if stage.after_moves > 9 {
+        uwrite!(lcd, "{}", stage.after_moves)?;
+    } else {
+        uwrite!(lcd, " {}", stage.after_moves)?;
+    }
+    start_row(1, geometry, lcd)?;
+    if stage.after_moves == 0 {
+        uwrite!(lcd, " (sudden death) ")?;
+    } else {
+        uwrite!(lcd, "+")?;
+        render_time_part(
+            &stage.time,
+            blink_off_part.is_some_and(|b| b == TimeSetPart::StageMin),
+            blink_off_part.is_some_and(|b| b == TimeSetPart::StageSec),
+            lcd,
+        )?;
+        uwrite!(lcd, "    ")?;
+        if blink_off_part.is_some_and(|b| b == TimeSetPart::StageInc) {
+            uwrite!(lcd, "    ")?;
+        } else {
+            render_increment_part(&stage.increment, mode, lcd)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders both lines of the set-up screens for overtime. `part` picks whether
+/// the kind of overtime or its settings are being changed.
+fn render_overtime<L: Lcd>(
+    part: TimeSetPart,
+    overtime: &Overtime,
+    blink_off_part: Option<TimeSetPart>,
+    geometry: &Geometry,
+    lcd: &mut L,
+) -> Result<(), L::Error> {
+    let mode_name = match overtime.mode {
+        OvertimeMode::None => "      None      ",
+        OvertimeMode::ByoYomi => "    Byo-yomi    ",
+        OvertimeMode::Canadian => "    Canadian    ",
+    };
+    start_row(0, geometry, lcd)?;
+    if part == TimeSetPart::SetOvertime {
+        uwrite!(lcd, "    Overtime    ")?;
+    } else {
+        uwrite!(lcd, "{}", mode_name)?;
+    }
+    start_row(1, geometry, lcd)?;
+    if part == TimeSetPart::SetOvertime {
+        if blink_off_part.is_some_and(|b| b == TimeSetPart::SetOvertime) {
+            uwrite!(lcd, "                ")?;
+        } else {
+            uwrite!(lcd, "{}", mode_name)?;
+        }
+        return Ok(());
+    }
+    // e.g. `     5 x 30s    ` for five periods of 30 seconds, or
+    // `  20 in 0:05:00 ` for 20 moves in 5 minutes
+    uwrite!(
+        lcd,
+        "{}",
+        if overtime.mode == OvertimeMode::Canadian {
+            "  "
+        } else {
+            "    "
+        }
+    )?;
+    if blink_off_part.is_some_and(|b| b == TimeSetPart::OvertimeCount) {
+        uwrite!(lcd, "  ")?;
+    } else if overtime.count > 9 {
+        uwrite!(lcd, "{}", overtime.count)?;
+    } else {
+        uwrite!(lcd, " {}", overtime.count)?;
+    }
+    if overtime.mode == OvertimeMode::Canadian {
+        uwrite!(lcd, " in ")?;
+        render_time_part(
+            &overtime.period,
+            blink_off_part.is_some_and(|b| b == TimeSetPart::OvertimeMin),
+            blink_off_part.is_some_and(|b| b == TimeSetPart::OvertimeSec),
+            lcd,
+        )?;
+        return uwrite!(lcd, " ");
+    }
+    uwrite!(lcd, " x ")?;
+    let secs = overtime.period.into_secs();
+    if blink_off_part.is_some_and(|b| b == TimeSetPart::OvertimeSec) {
+        uwrite!(lcd, "  ")?;
+    } else if secs > 9 {
+        uwrite!(lcd, "{}", secs)?;
+    } else {
+        uwrite!(lcd, " {}", secs)?;
+    }
+    uwrite!(lcd, "s    ")
+}
@@ -0,0 +1,403 @@
+//! The screens on a 128x64 OLED like the SSD1306: the set-up screens and
+//! banners as text, and the clocks in big digits over a bar showing how much
+//! of each player's time is left.
+
+use core::convert::Infallible;
+
+use ufmt::uwrite;
+
+use crate::{
+    countdown::{Phase, TimeControlMode, Turn},
+    display::{
+        char_lcd::{render_clock, render_moves, render_status},
+        font, Banner, CharLcd, ClockDisplay, Connection, Layout, Setting,
+    },
+    hal::{uWrite, Lcd, PagedDisplay},
+    time_set::{Increment, TimeSetPart, TimeSetting},
+    LCD_LINE_LENGTH,
+};
+
+/// The number of columns of pixels.
+const WIDTH: usize = 128;
+const PAGES: u8 = 8;
+/// The width of a character of text, with the gap after it.
+const CHAR_WIDTH: usize = 6;
+/// Where each line of text starts, which centres the 16 characters of the
+/// character LCD's screens.
+const TEXT_LEFT: usize = 16;
+/// The pages for the two lines of the set-up screens.
+const SETTING_PAGES: [u8; 2] = [2, 4];
+/// The pages for the line above the clocks and the line below them.
+const CLOCK_PAGES: [u8; 2] = [0, 7];
+/// The first of the three pages each player's clock takes up.
+const PLAYER_PAGES: (u8, u8) = (1, 4);
+/// Where the clocks start, after the marker showing whose is running.
+const CLOCK_LEFT: usize = 10;
+/// How many times the size of text the clocks are, unless they don't fit.
+const BIG_SCALE: usize = 3;
+/// How many times the size of text the waiting player's clock is.
+const SMALL_SCALE: usize = 2;
+/// The bottom of the digits, which all scales line up on, leaving a gap above
+/// the bar under them.
+const BASELINE: usize = 7 * BIG_SCALE;
+
+/// Text in the 5x7 font, on two of the pages at a time, so the screens can be
+/// drawn the same way as on a character LCD.
+#[derive(Debug)]
+pub struct OledText<P: PagedDisplay> {
+    pub display: P,
+    pages: [u8; 2],
+    page: u8,
+    column: usize,
+}
+
+impl<P: PagedDisplay> uWrite for OledText<P> {
+    type Error = P::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), P::Error> {
+        for byte in s.bytes() {
+            // Anything off the edge is lost, like on a character LCD
+            if self.column + CHAR_WIDTH > WIDTH {
+                break;
+            }
+            let mut columns = [0; CHAR_WIDTH];
+            columns[..5].copy_from_slice(font::glyph(byte));
+            self.display
+                .write_page(self.page, self.column as u8, &columns)?;
+            self.column += CHAR_WIDTH;
+        }
+        Ok(())
+    }
+}
+
+impl<P: PagedDisplay> Lcd for OledText<P> {
+    fn clear(&mut self) -> Result<(), P::Error> {
+        for page in 0..PAGES {
+            self.display.write_page(page, 0, &[0; WIDTH])?;
+        }
+        Ok(())
+    }
+
+    fn set_cursor_pos(&mut self, position: u8) -> Result<(), P::Error> {
+        self.page = self.pages[(position / LCD_LINE_LENGTH) as usize % 2];
+        self.column = TEXT_LEFT + (position % LCD_LINE_LENGTH) as usize * CHAR_WIDTH;
+        Ok(())
+    }
+
+    fn define_char(&mut self, _index: u8, _rows: &[u8; 8]) -> Result<(), P::Error> {
+        // Nothing needs custom characters with every pixel to draw on
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Screen {
+    /// The splash, set-up and error screens
+    Text,
+    /// Both clocks, while the game is on
+    Clocks,
+}
+
+/// Draws the screens on a 128x64 OLED.
+#[derive(Debug)]
+pub struct Oled<P: PagedDisplay> {
+    text: CharLcd<OledText<P>>,
+    /// What's on the display now, if anything
+    screen: Option<Screen>,
+    /// The time each player started with in ms, for the bars under the clocks
+    start_ms: (u32, u32),
+}
+
+impl<P: PagedDisplay> Oled<P> {
+    pub fn new(display: P) -> Self {
+        Oled {
+            text: CharLcd::new(OledText {
+                display,
+                pages: SETTING_PAGES,
+                page: 0,
+                column: 0,
+            }),
+            screen: None,
+            start_ms: (0, 0),
+        }
+    }
+
+    pub fn display(&self) -> &P {
+        &self.text.lcd.display
+    }
+
+    /// Clears the display when going from one kind of screen to the other, and
+    /// moves the text to wherever it goes on the new one.
+    fn show(&mut self, screen: Screen) -> Result<(), P::Error> {
+        if self.screen != Some(screen) {
+            self.text.lcd.clear()?;
+            self.text.lcd.pages = match screen {
+                Screen::Text => SETTING_PAGES,
+                Screen::Clocks => CLOCK_PAGES,
+            };
+            self.screen = Some(screen);
+        }
+        Ok(())
+    }
+
+    /// Draws both clocks, the running one (if any) big with a marker.
+    fn draw_clocks(
+        &mut self,
+        running: Option<&Turn>,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), P::Error> {
+        let scale = |turn| match running {
+            Some(running) if *running != turn => SMALL_SCALE,
+            _ => BIG_SCALE,
+        };
+        let display = &mut self.text.lcd.display;
+        render_big_clock(
+            p1_time,
+            &phases.0,
+            self.start_ms.0,
+            running == Some(&Turn::P1),
+            scale(Turn::P1),
+            PLAYER_PAGES.0,
+            display,
+        )?;
+        render_big_clock(
+            p2_time,
+            &phases.1,
+            self.start_ms.1,
+            running == Some(&Turn::P2),
+            scale(Turn::P2),
+            PLAYER_PAGES.1,
+            display,
+        )
+    }
+}
+
+impl<P: PagedDisplay> ClockDisplay for Oled<P> {
+    type Error = P::Error;
+
+    fn set_start_times(
+        &mut self,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+    ) -> Result<(), P::Error> {
+        self.start_ms = (p1_time.into_millis(), p2_time.into_millis());
+        Ok(())
+    }
+
+    fn draw_splash(&mut self, version: &str) -> Result<(), P::Error> {
+        self.show(Screen::Text)?;
+        self.text.draw_splash(version)
+    }
+
+    fn draw_connection(&mut self, connection: Connection) -> Result<(), P::Error> {
+        self.text.draw_connection(connection)
+    }
+
+    fn draw_layout(&mut self, layout: Layout) -> Result<(), P::Error> {
+        self.text.draw_layout(layout)
+    }
+
+    fn draw_setting(
+        &mut self,
+        setting: &Setting,
+        blink_off_part: Option<TimeSetPart>,
+    ) -> Result<(), P::Error> {
+        self.show(Screen::Text)?;
+        self.text.draw_setting(setting, blink_off_part)
+    }
+
+    fn draw_countdown(
+        &mut self,
+        turn: &Turn,
+        delay_secs: Option<u8>,
+        stage_note: Option<usize>,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), P::Error> {
+        self.show(Screen::Clocks)?;
+        let lcd = &mut self.text.lcd;
+        lcd.set_cursor_pos(0)?;
+        render_status(turn, delay_secs, stage_note, lcd)?;
+        self.draw_clocks(Some(turn), p1_time, p2_time, phases)
+    }
+
+    fn draw_times(
+        &mut self,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), P::Error> {
+        self.show(Screen::Clocks)?;
+        self.draw_clocks(None, p1_time, p2_time, phases)
+    }
+
+    fn draw_details(
+        &mut self,
+        moves: &(u16, u16),
+        _increments: (&Increment, &Increment),
+        _mode: &TimeControlMode,
+    ) -> Result<(), P::Error> {
+        self.show(Screen::Clocks)?;
+        let lcd = &mut self.text.lcd;
+        lcd.set_cursor_pos(LCD_LINE_LENGTH * 1)?;
+        render_moves(moves, lcd)
+    }
+
+    fn draw_banner(&mut self, banner: &Banner) -> Result<(), P::Error> {
+        self.show(Screen::Clocks)?;
+        self.text.draw_banner(banner)
+    }
+
+    fn draw_error(&mut self, name: &str) -> Result<(), P::Error> {
+        self.show(Screen::Text)?;
+        self.text.draw_error(name)
+    }
+}
+
+/// Up to 16 characters of text, to be measured before it's drawn.
+struct TextBuffer {
+    bytes: [u8; 16],
+    len: usize,
+}
+
+impl uWrite for TextBuffer {
+    type Error = Infallible;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+        for byte in s.bytes() {
+            if self.len < self.bytes.len() {
+                self.bytes[self.len] = byte;
+                self.len += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Lcd for TextBuffer {
+    fn clear(&mut self) -> Result<(), Infallible> {
+        self.len = 0;
+        Ok(())
+    }
+
+    fn set_cursor_pos(&mut self, _position: u8) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn define_char(&mut self, _index: u8, _rows: &[u8; 8]) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Renders a player's clock across three pages from `first_page`: their time
+/// in proportional digits `scale` times the size of text, a marker in front if
+/// it's running, and a bar along the bottom as long as the share of `start_ms`
+/// they have left.
+fn render_big_clock<P: PagedDisplay>(
+    time: &TimeSetting,
+    phase: &Phase,
+    start_ms: u32,
+    running: bool,
+    scale: usize,
+    first_page: u8,
+    display: &mut P,
+) -> Result<(), P::Error> {
+    // The same as on a character LCD, but without the hours when there are
+    // none since there's no other clock to line up with
+    let mut text = TextBuffer {
+        bytes: [0; 16],
+        len: 0,
+    };
+    let _ = match *phase {
+        Phase::Main if time.into_secs() < 60 * 60 => {
+            let (_, mins, secs) = time.into_hrs_mins_secs();
+            if secs > 9 {
+                uwrite!(text, "{}:{}", mins, secs)
+            } else {
+                uwrite!(text, "{}:0{}", mins, secs)
+            }
+        }
+        _ => render_clock(time, phase, &mut text),
+    };
+    let text = &text.bytes[..text.len];
+    let start = text
+        .iter()
+        .position(|&byte| byte != b' ')
+        .unwrap_or(text.len());
+    let end = text
+        .iter()
+        .rposition(|&byte| byte != b' ')
+        .map_or(start, |end| end + 1);
+    let text = &text[start..end];
+
+    // Shrink anything too long to fit, like overtime
+    let width = |scale: usize| {
+        text.iter()
+            .map(|&byte| (font::trimmed_glyph(byte).len() + 1) * scale)
+            .sum::<usize>()
+    };
+    let mut scale = scale;
+    while scale > 1 && CLOCK_LEFT + width(scale) > WIDTH + scale {
+        scale -= 1;
+    }
+    let top = BASELINE - 7 * scale;
+
+    // The time per column, rounded down so the bar is full at the start. This
+    // keeps the sums in 32 bits, which a multiplication by the width wouldn't.
+    let ms_per_column = (start_ms / WIDTH as u32).max(1);
+    let fill = ((time.into_millis() / ms_per_column) as usize).min(WIDTH);
+
+    for page in 0..3 {
+        let mut columns = [0; WIDTH];
+        if running {
+            // A triangle pointing at the clock
+            for (x, column) in columns[..8].iter_mut().enumerate() {
+                *column = span(page, 3 + x, 18 - x);
+            }
+        }
+        let mut x = CLOCK_LEFT;
+        'text: for &byte in text {
+            for &glyph_column in font::trimmed_glyph(byte) {
+                let column = scale_column(glyph_column, scale, page, top);
+                for _ in 0..scale {
+                    if x >= WIDTH {
+                        break 'text;
+                    }
+                    columns[x] = column;
+                    x += 1;
+                }
+            }
+            x += scale;
+        }
+        if page == 2 {
+            // The bar along the bottom, with a thin line for the time used
+            for (x, column) in columns.iter_mut().enumerate() {
+                *column |= if x < fill { 0b1100_0000 } else { 0b1000_0000 };
+            }
+        }
+        display.write_page(first_page + page as u8, 0, &columns)?;
+    }
+    Ok(())
+}
+
+/// The part of `page` of a clock which the pixel rows from `top` to `bottom`
+/// (counting from the top of the clock, both included) cover.
+fn span(page: usize, top: usize, bottom: usize) -> u8 {
+    (0..8)
+        .filter(|bit| (top..=bottom).contains(&(page * 8 + bit)))
+        .fold(0, |column, bit| column | 1 << bit)
+}
+
+/// The part of `page` of a clock covered by a column of a character, scaled up
+/// `scale` times with its top at pixel row `top`.
+fn scale_column(glyph_column: u8, scale: usize, page: usize, top: usize) -> u8 {
+    (0..8)
+        .filter(|bit| {
+            let y = page * 8 + bit;
+            y >= top && (y - top) / scale < 7 && glyph_column >> ((y - top) / scale) & 1 != 0
+        })
+        .fold(0, |column, bit| column | 1 << bit)
+}
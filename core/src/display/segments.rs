@@ -0,0 +1,344 @@
+//! The clocks on a four-digit seven-segment display facing each player, like a
+//! real tournament clock, with another display for everything else if there's
+//! one.
+
+use core::convert::Infallible;
+
+use crate::{
+    countdown::{Phase, TimeControlMode, Turn},
+    display::{Banner, ClockDisplay, Connection, Layout, Setting},
+    hal::SegmentDisplays,
+    time_set::{Increment, TimeSetPart, TimeSetting},
+};
+
+/// The segments of 0 to 9, with segment A in bit 0 through to G in bit 6.
+const DIGITS: [u8; 10] = [0x3f, 0x06, 0x5b, 0x4f, 0x66, 0x6d, 0x7d, 0x07, 0x7f, 0x6f];
+const BLANK: u8 = 0;
+const DASH: u8 = 0x40;
+/// The decimal point after a digit.
+const POINT: u8 = 0x80;
+/// A lowercase `h`, between the hours and minutes.
+const HOURS: u8 = 0x74;
+const ERROR: [u8; 4] = [0x79, 0x50, 0x50, BLANK];
+
+/// Something going wrong with either the segment displays or the status
+/// display.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SegmentsError<S, D> {
+    Segments(S),
+    Status(D),
+}
+
+/// The status display of a clock with only segment displays, which shows
+/// nothing.
+#[derive(Debug)]
+pub struct NoStatus;
+
+impl ClockDisplay for NoStatus {
+    type Error = Infallible;
+
+    fn draw_splash(&mut self, _version: &str) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_connection(&mut self, _connection: Connection) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_layout(&mut self, _layout: Layout) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_setting(
+        &mut self,
+        _setting: &Setting,
+        _blink_off_part: Option<TimeSetPart>,
+    ) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_countdown(
+        &mut self,
+        _turn: &Turn,
+        _delay_secs: Option<u8>,
+        _stage_note: Option<usize>,
+        _p1_time: &TimeSetting,
+        _p2_time: &TimeSetting,
+        _phases: &(Phase, Phase),
+    ) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_times(
+        &mut self,
+        _p1_time: &TimeSetting,
+        _p2_time: &TimeSetting,
+        _phases: &(Phase, Phase),
+    ) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_banner(&mut self, _banner: &Banner) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn draw_error(&mut self, _name: &str) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// Shows each player's clock on the segment display facing them, and
+/// everything else on `status`, such as a character LCD.
+///
+/// The colon is lit while a clock is running or both are stopped, and goes out
+/// on the clock of the player waiting for their turn. Under ten seconds a clock
+/// shows tenths after a decimal point, and from an hour up it shows hours and
+/// minutes. Either way, there's no colon, so the decimal point after the last
+/// digit is lit instead while it's running.
+#[derive(Debug)]
+pub struct SegmentClocks<S: SegmentDisplays, D: ClockDisplay> {
+    pub segments: S,
+    pub status: D,
+}
+
+impl<S: SegmentDisplays, D: ClockDisplay> SegmentClocks<S, D> {
+    pub fn new(segments: S, status: D) -> Self {
+        SegmentClocks { segments, status }
+    }
+
+    /// Shows the same thing facing both players.
+    fn show_both(
+        &mut self,
+        digits: &[u8; 4],
+        colon: bool,
+    ) -> Result<(), SegmentsError<S::Error, D::Error>> {
+        for player in [Turn::P1, Turn::P2] {
+            self.segments
+                .show(&player, digits, colon)
+                .map_err(SegmentsError::Segments)?;
+        }
+        Ok(())
+    }
+
+    /// Shows each player's clock, with `running` the one counting down if
+    /// any.
+    fn show_clocks(
+        &mut self,
+        running: Option<&Turn>,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), SegmentsError<S::Error, D::Error>> {
+        for (player, time, phase) in [
+            (Turn::P1, p1_time, &phases.0),
+            (Turn::P2, p2_time, &phases.1),
+        ] {
+            let (mut digits, colon) = render_time(time, phase);
+            let colon = match running {
+                None => colon,
+                Some(running) if *running != player => false,
+                Some(_) if colon => true,
+                // Without a colon to light, the last decimal point is lit
+                Some(_) => {
+                    digits[3] |= POINT;
+                    false
+                }
+            };
+            self.segments
+                .show(&player, &digits, colon)
+                .map_err(SegmentsError::Segments)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: SegmentDisplays, D: ClockDisplay> ClockDisplay for SegmentClocks<S, D> {
+    type Error = SegmentsError<S::Error, D::Error>;
+
+    fn shows_tenths(&self) -> bool {
+        true
+    }
+
+    fn set_layout(&mut self, layout: Layout) -> Result<(), Self::Error> {
+        self.status
+            .set_layout(layout)
+            .map_err(SegmentsError::Status)
+    }
+
+    fn set_start_times(
+        &mut self,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+    ) -> Result<(), Self::Error> {
+        self.status
+            .set_start_times(p1_time, p2_time)
+            .map_err(SegmentsError::Status)
+    }
+
+    fn draw_splash(&mut self, version: &str) -> Result<(), Self::Error> {
+        self.status
+            .draw_splash(version)
+            .map_err(SegmentsError::Status)?;
+        self.show_both(&[DASH; 4], false)
+    }
+
+    fn draw_connection(&mut self, connection: Connection) -> Result<(), Self::Error> {
+        self.status
+            .draw_connection(connection)
+            .map_err(SegmentsError::Status)
+    }
+
+    fn draw_layout(&mut self, layout: Layout) -> Result<(), Self::Error> {
+        self.status
+            .draw_layout(layout)
+            .map_err(SegmentsError::Status)
+    }
+
+    fn draw_setting(
+        &mut self,
+        setting: &Setting,
+        blink_off_part: Option<TimeSetPart>,
+    ) -> Result<(), Self::Error> {
+        self.status
+            .draw_setting(setting, blink_off_part)
+            .map_err(SegmentsError::Status)?;
+        let Setting::Times(p1_time, p2_time) = *setting else {
+            return self.show_both(&[DASH; 4], false);
+        };
+        // Each player sees the time they're being given, blinking along with
+        // the status display
+        let parts = [
+            (
+                Turn::P1,
+                p1_time,
+                [TimeSetPart::P1SetMin, TimeSetPart::P1SetSec],
+            ),
+            (
+                Turn::P2,
+                p2_time,
+                [TimeSetPart::P2SetMin, TimeSetPart::P2SetSec],
+            ),
+        ];
+        for (player, time, parts) in parts {
+            let (digits, colon) = match blink_off_part {
+                Some(part) if parts.contains(&part) => ([BLANK; 4], false),
+                _ => render_time(time, &Phase::Main),
+            };
+            self.segments
+                .show(&player, &digits, colon)
+                .map_err(SegmentsError::Segments)?;
+        }
+        Ok(())
+    }
+
+    fn draw_countdown(
+        &mut self,
+        turn: &Turn,
+        delay_secs: Option<u8>,
+        stage_note: Option<usize>,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), Self::Error> {
+        self.status
+            .draw_countdown(turn, delay_secs, stage_note, p1_time, p2_time, phases)
+            .map_err(SegmentsError::Status)?;
+        self.show_clocks(Some(turn), p1_time, p2_time, phases)
+    }
+
+    fn draw_times(
+        &mut self,
+        p1_time: &TimeSetting,
+        p2_time: &TimeSetting,
+        phases: &(Phase, Phase),
+    ) -> Result<(), Self::Error> {
+        self.status
+            .draw_times(p1_time, p2_time, phases)
+            .map_err(SegmentsError::Status)?;
+        self.show_clocks(None, p1_time, p2_time, phases)
+    }
+
+    fn draw_details(
+        &mut self,
+        moves: &(u16, u16),
+        increments: (&Increment, &Increment),
+        mode: &TimeControlMode,
+    ) -> Result<(), Self::Error> {
+        self.status
+            .draw_details(moves, increments, mode)
+            .map_err(SegmentsError::Status)
+    }
+
+    fn draw_banner(&mut self, banner: &Banner) -> Result<(), Self::Error> {
+        self.status
+            .draw_banner(banner)
+            .map_err(SegmentsError::Status)
+    }
+
+    fn draw_error(&mut self, name: &str) -> Result<(), Self::Error> {
+        // Show it on the segments even if the status display is what failed
+        let status = self.status.draw_error(name);
+        self.show_both(&ERROR, false)?;
+        status.map_err(SegmentsError::Status)
+    }
+}
+
+/// The digits showing `time` on a four-digit display, and whether they need
+/// the colon: `M:SS` or `MM:SS`, `HhMM` from an hour up and `S.t` under ten
+/// seconds. In byo-yomi, the periods left come first, as in `3-30` or `3-9.4`.
+fn render_time(time: &TimeSetting, phase: &Phase) -> ([u8; 4], bool) {
+    let tenths = time.into_tenths();
+    let secs = time.into_secs();
+    match *phase {
+        Phase::ByoYomi { periods } if tenths < 10 * 10 => (
+            [
+                digit(periods.min(9) as u32),
+                DASH,
+                digit(tenths / 10) | POINT,
+                digit(tenths % 10),
+            ],
+            false,
+        ),
+        Phase::ByoYomi { periods } => (
+            [
+                digit(periods.min(9) as u32),
+                DASH,
+                digit(secs.min(99) / 10),
+                digit(secs.min(99) % 10),
+            ],
+            false,
+        ),
+        _ if tenths < 10 * 10 => (
+            [BLANK, BLANK, digit(tenths / 10) | POINT, digit(tenths % 10)],
+            false,
+        ),
+        _ if secs >= 60 * 60 => {
+            let (hrs, mins, _) = time.into_hrs_mins_secs();
+            (
+                [
+                    digit(hrs.min(9) as u32),
+                    HOURS,
+                    digit(mins as u32 / 10),
+                    digit(mins as u32 % 10),
+                ],
+                false,
+            )
+        }
+        _ => {
+            let (mins, secs) = (secs / 60, secs % 60);
+            (
+                [
+                    if mins > 9 { digit(mins / 10) } else { BLANK },
+                    digit(mins % 10),
+                    digit(secs / 10),
+                    digit(secs % 10),
+                ],
+                true,
+            )
+        }
+    }
+}
+
+fn digit(value: u32) -> u8 {
+    DIGITS[value as usize % 10]
+}
@@ -0,0 +1,17 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RuntimeError {
+    DisplayError,
+    PinReadError,
+    PinWriteError,
+}
+
+impl RuntimeError {
+    /// The name of the error, for showing on the display.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuntimeError::DisplayError => "DisplayError",
+            RuntimeError::PinReadError => "PinReadError",
+            RuntimeError::PinWriteError => "PinWriteError",
+        }
+    }
+}
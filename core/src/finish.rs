@@ -0,0 +1,95 @@
+use debouncr::{debounce_4, Edge};
+
+use crate::{
+    countdown::{Phase, TimeControl, Turn},
+    display::{Banner, ClockDisplay},
+    error::RuntimeError,
+    hal::{Button, Buzzer, SerialPort, TimeSource},
+    serial::{GameState, SerialHandler},
+    time_set::TimeSetting,
+};
+
+const LOOP_DELAY: u16 = 5;
+const BUZZER_LENGTH: u16 = 120;
+const BLINK_DURATION: u16 = 400;
+
+pub fn finish<SP: Button, BP: Buzzer, S: SerialPort, T: TimeSource, D: ClockDisplay>(
+    loser: &Turn,
+    times: &(TimeSetting, TimeSetting),
+    control: &TimeControl,
+    moves: &(u16, u16),
+    phases: &(Phase, Phase),
+    serial_handler: &mut SerialHandler<S>,
+    time: &mut T,
+    display: &mut D,
+    start_pin: &mut SP,
+    buzzer_pin: &mut BP,
+) -> Result<(), RuntimeError> {
+    buzzer_pin
+        .set_on()
+        .map_err(|_| RuntimeError::PinWriteError)?;
+
+    // The countdown may have been using the whole display
+    display
+        .draw_times(&times.0, &times.1, phases)
+        .map_err(|_| RuntimeError::DisplayError)?;
+    display
+        .draw_details(moves, control.increments(moves), &control.mode)
+        .map_err(|_| RuntimeError::DisplayError)?;
+
+    let mut start = debounce_4(false);
+    let mut i = 0;
+    let mut blink_count = 0;
+    let mut last_blink = u8::MAX;
+    loop {
+        // Alternate between the result and the number of moves
+        blink_count += 1;
+        if blink_count >= BLINK_DURATION * 2 {
+            blink_count = 0;
+        }
+        let blink = (blink_count / BLINK_DURATION) as u8;
+        if blink != last_blink {
+            display
+                .draw_banner(&if blink == 1 {
+                    Banner::Moves(moves)
+                } else {
+                    Banner::TimeUp(loser.clone())
+                })
+                .map_err(|_| RuntimeError::DisplayError)?;
+            last_blink = blink;
+        }
+
+        // Nothing from the other side can change the result, but whoever
+        // connects gets it
+        serial_handler.read();
+        if serial_handler.keep_alive(time) {
+            serial_handler.send_game(
+                control,
+                (times.0.into_millis(), times.1.into_millis()),
+                moves,
+                phases,
+                GameState::Finished(loser.clone()),
+            );
+        }
+
+        if i < BUZZER_LENGTH {
+            i += 1;
+        } else {
+            buzzer_pin
+                .set_off()
+                .map_err(|_| RuntimeError::PinWriteError)?;
+        }
+        if start.update(
+            start_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Falling)
+        {
+            // Start button released; continue. Waiting for the release means
+            // the set-up screen doesn't see it and pick a preset straight away
+            break;
+        }
+        time.delay_ms(LOOP_DELAY);
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! The hardware the clock needs, kept as small traits so the same screens can
+//! run on the board, in the simulator or in tests.
+
+pub use ufmt::uWrite;
+
+use crate::countdown::Turn;
+
+/// A free-running millisecond counter plus a way to wait.
+pub trait TimeSource {
+    /// Milliseconds since some fixed point, like Arduino's `millis()`. Wraps
+    /// around after about 49 days.
+    fn millis(&self) -> u32;
+
+    /// Blocks for `ms` milliseconds.
+    fn delay_ms(&mut self, ms: u16);
+}
+
+/// A push button.
+pub trait Button {
+    type Error;
+
+    /// Whether the button is held down right now, before any debouncing.
+    fn is_pressed(&mut self) -> Result<bool, Self::Error>;
+}
+
+/// The buzzer, which is either sounding or not.
+pub trait Buzzer {
+    type Error;
+
+    fn set_on(&mut self) -> Result<(), Self::Error>;
+
+    fn set_off(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A character LCD addressed like the HD44780: the second line starts at
+/// [`LCD_LINE_LENGTH`](crate::LCD_LINE_LENGTH). Text is written at the cursor
+/// with `ufmt`, since `core::fmt` is far too big for the AVR.
+pub trait Lcd: uWrite {
+    fn clear(&mut self) -> Result<(), Self::Error>;
+
+    fn set_cursor_pos(&mut self, position: u8) -> Result<(), Self::Error>;
+
+    /// Sets custom character `index` (0 to 7) to a 5x8 bitmap, one byte per
+    /// row from the top with the leftmost pixel in bit 4. It's then written
+    /// like any other character, as the byte `index`.
+    fn define_char(&mut self, index: u8, rows: &[u8; 8]) -> Result<(), Self::Error>;
+}
+
+/// A 128x64 monochrome display written a page at a time, like the SSD1306. A
+/// page is a strip eight pixels tall across the whole width, and each byte is
+/// one column of it with the top pixel in bit 0.
+pub trait PagedDisplay {
+    type Error;
+
+    /// Writes `columns` to `page` (0 to 7) from `column` (0 to 127) onwards.
+    fn write_page(&mut self, page: u8, column: u8, columns: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// A four-digit seven-segment display facing each player, like a pair of
+/// TM1637 or MAX7219 modules.
+pub trait SegmentDisplays {
+    type Error;
+
+    /// Shows `digits` from left to right on `player`'s display. Each has
+    /// segments A to G in bits 0 to 6 and the decimal point after it in bit 7.
+    /// `colon` lights the colon between the second and third digits.
+    fn show(&mut self, player: &Turn, digits: &[u8; 4], colon: bool) -> Result<(), Self::Error>;
+}
+
+/// The serial connection to the website.
+pub trait SerialPort {
+    /// Reads a byte if one has arrived, without waiting for one.
+    fn read_byte(&mut self) -> Option<u8>;
+
+    /// Writes a byte, blocking until there's room for it.
+    fn write_byte(&mut self, byte: u8);
+}
+
+/// Byte-addressed non-volatile memory, like the ATmega328P's EEPROM.
+pub trait Eeprom {
+    fn read_byte(&self, offset: u16) -> u8;
+
+    fn write_byte(&mut self, offset: u16, byte: u8);
+}
@@ -0,0 +1,223 @@
+//! The rules and screens of the open chess clock, independent of any hardware.
+//!
+//! Everything the clock touches is behind the small traits in [`hal`], so the
+//! firmware only has to provide those and call [`run`].
+
+#![no_std]
+// The screens take every bit of hardware they use as a parameter
+#![allow(clippy::too_many_arguments)]
+// `LCD_LINE_LENGTH * 1` reads as "the second line"
+#![allow(clippy::identity_op)]
+#![allow(clippy::new_without_default)]
+// `div_ceil` and `is_multiple_of` are newer than the firmware's toolchain
+#![allow(clippy::manual_div_ceil, clippy::manual_is_multiple_of)]
+
+use core::convert::Infallible;
+
+use countdown::Turn;
+use display::{ClockDisplay, Connection};
+use error::RuntimeError;
+use hal::{Button, Buzzer, Eeprom, SerialPort, TimeSource};
+use serial::{SerialHandler, SerialMsg};
+use storage::Storage;
+
+pub mod countdown;
+pub mod display;
+pub mod error;
+pub mod finish;
+pub mod hal;
+pub mod pause;
+pub mod presets;
+pub mod serial;
+pub mod storage;
+pub mod time_set;
+
+/// Where the second line of a character LCD starts.
+pub const LCD_LINE_LENGTH: u8 = 40;
+const SPLASH_DURATION: u16 = 1500;
+const CONNECTION_TIMEOUT_MS: u16 = 500;
+const MSG_DURATION: u16 = 1500;
+const BUTTON_POLL_MS: u16 = 5;
+
+/// Runs the clock forever, from the splash screen through as many games as
+/// are played. Only returns if something goes wrong.
+pub fn run<
+    DP: Button,
+    UP: Button,
+    SP: Button,
+    BP: Buzzer,
+    S: SerialPort,
+    E: Eeprom,
+    T: TimeSource,
+    D: ClockDisplay,
+>(
+    mut down_btn: DP,
+    mut up_btn: UP,
+    mut start_btn: SP,
+    mut buzzer: BP,
+    mut serial_handler: SerialHandler<S>,
+    mut storage: Storage<E>,
+    time: &mut T,
+    display: &mut D,
+    version: &str,
+) -> Result<Infallible, RuntimeError> {
+    let mut layout = storage.load_layout();
+    display
+        .set_layout(layout)
+        .map_err(|_| RuntimeError::DisplayError)?;
+
+    // Show the splash screen
+    display
+        .draw_splash(version)
+        .map_err(|_| RuntimeError::DisplayError)?;
+    time.delay_ms(SPLASH_DURATION);
+
+    // Holding START through the splash screen switches the countdown layout
+    if start_btn
+        .is_pressed()
+        .map_err(|_| RuntimeError::PinReadError)?
+    {
+        layout = layout.next();
+        storage.save_layout(layout);
+        display
+            .set_layout(layout)
+            .map_err(|_| RuntimeError::DisplayError)?;
+        display
+            .draw_layout(layout)
+            .map_err(|_| RuntimeError::DisplayError)?;
+        // Wait for it to be let go, or the set-up screen would see it
+        while start_btn
+            .is_pressed()
+            .map_err(|_| RuntimeError::PinReadError)?
+        {
+            time.delay_ms(BUTTON_POLL_MS);
+        }
+        time.delay_ms(MSG_DURATION);
+    }
+    display
+        .draw_connection(Connection::Connecting)
+        .map_err(|_| RuntimeError::DisplayError)?;
+    let connected = loop {
+        if let Some(connected) =
+            serial_handler.check_connection(CONNECTION_TIMEOUT_MS, version, time)
+        {
+            break connected;
+        }
+        time.delay_ms(BUTTON_POLL_MS);
+    };
+    display
+        .draw_connection(if connected {
+            Connection::Connected
+        } else {
+            Connection::NotConnected
+        })
+        .map_err(|_| RuntimeError::DisplayError)?;
+    time.delay_ms(MSG_DURATION);
+
+    'main: loop {
+        // Prompt the user to set up the time
+        let (mut times, control) = time_set::time_set(
+            &mut down_btn,
+            &mut up_btn,
+            &mut start_btn,
+            &mut serial_handler,
+            &mut storage,
+            time,
+            display,
+        )?;
+        display
+            .set_start_times(&times.0, &times.1)
+            .map_err(|_| RuntimeError::DisplayError)?;
+        let mut moves = (0, 0);
+        let mut phases = (countdown::Phase::Main, countdown::Phase::Main);
+        let mut turn = match pause::pause(
+            &mut down_btn,
+            &mut up_btn,
+            &mut start_btn,
+            &mut serial_handler,
+            time,
+            display,
+            &mut times.0,
+            &mut times.1,
+            &control,
+            &mut moves,
+            &phases,
+            None,
+        )? {
+            pause::PauseResult::ResumedP1 => Turn::P1,
+            pause::PauseResult::ResumedP2 => Turn::P2,
+            pause::PauseResult::Stopped => continue 'main,
+        };
+        let loser = loop {
+            serial_handler.write(match turn {
+                Turn::P1 => SerialMsg::StartP1 {
+                    p2_time: times.1.into_millis(),
+                    move_number: moves.0 as u32 + 1,
+                },
+                Turn::P2 => SerialMsg::StartP2 {
+                    p1_time: times.0.into_millis(),
+                    move_number: moves.1 as u32 + 1,
+                },
+            });
+            match countdown::countdown(
+                &mut down_btn,
+                &mut up_btn,
+                &mut start_btn,
+                &mut buzzer,
+                &mut serial_handler,
+                time,
+                display,
+                &mut times.0,
+                &mut times.1,
+                &control,
+                &mut moves,
+                &mut phases,
+                &mut turn,
+            )? {
+                countdown::CountdownResult::FinishedP1 => break Turn::P1,
+                countdown::CountdownResult::FinishedP2 => break Turn::P2,
+                countdown::CountdownResult::Paused => (),
+            }
+            serial_handler.write(SerialMsg::Pause {
+                time: match turn {
+                    Turn::P1 => times.0.into_millis(),
+                    Turn::P2 => times.1.into_millis(),
+                },
+            });
+            match pause::pause(
+                &mut down_btn,
+                &mut up_btn,
+                &mut start_btn,
+                &mut serial_handler,
+                time,
+                display,
+                &mut times.0,
+                &mut times.1,
+                &control,
+                &mut moves,
+                &phases,
+                Some(&turn),
+            )? {
+                pause::PauseResult::ResumedP1 => turn = Turn::P1,
+                pause::PauseResult::ResumedP2 => turn = Turn::P2,
+                pause::PauseResult::Stopped => continue 'main,
+            }
+        };
+        serial_handler.write(match loser {
+            Turn::P1 => SerialMsg::P1Finish,
+            Turn::P2 => SerialMsg::P2Finish,
+        });
+        finish::finish(
+            &loser,
+            &times,
+            &control,
+            &moves,
+            &phases,
+            &mut serial_handler,
+            time,
+            display,
+            &mut start_btn,
+            &mut buzzer,
+        )?;
+    }
+}
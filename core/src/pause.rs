@@ -0,0 +1,172 @@
+use debouncr::{debounce_4, Edge};
+
+use crate::{
+    countdown::{Phase, TimeControl, Turn},
+    display::{Banner, ClockDisplay},
+    error::RuntimeError,
+    hal::{Button, SerialPort, TimeSource},
+    serial::{GameState, Role, SerialHandler, SerialMsg},
+    time_set::TimeSetting,
+};
+
+const BLINK_DURATION: u16 = 400;
+const LOOP_DELAY: u16 = 5;
+
+pub enum PauseResult {
+    ResumedP1,
+    ResumedP2,
+    Stopped,
+}
+
+pub fn pause<DP: Button, UP: Button, SP: Button, S: SerialPort, T: TimeSource, D: ClockDisplay>(
+    down_pin: &mut DP,
+    up_pin: &mut UP,
+    start_pin: &mut SP,
+    serial_handler: &mut SerialHandler<S>,
+    time: &mut T,
+    display: &mut D,
+    p1_time: &mut TimeSetting,
+    p2_time: &mut TimeSetting,
+    control: &TimeControl,
+    moves: &mut (u16, u16),
+    phases: &(Phase, Phase),
+    turn: Option<&Turn>,
+) -> Result<PauseResult, RuntimeError> {
+    // `turn` is whose move it is, which nobody has until the game starts
+    let initial_pause = turn.is_none();
+    let mut down = debounce_4(false);
+    let mut up = debounce_4(false);
+    let mut start = debounce_4(false);
+
+    display
+        .draw_times(p1_time, p2_time, phases)
+        .map_err(|_| RuntimeError::DisplayError)?;
+    display
+        .draw_details(moves, control.increments(moves), &control.mode)
+        .map_err(|_| RuntimeError::DisplayError)?;
+
+    // Nobody has moved yet at the start, so there's no point showing moves
+    let banner_count = if initial_pause { 3 } else { 4 };
+    let mut blink_count = 0;
+    let mut last_blink = u8::MAX;
+    Ok(loop {
+        // Change blinks
+        blink_count += 1;
+        if blink_count >= BLINK_DURATION * banner_count {
+            blink_count = 0;
+        }
+        let blink = (blink_count / BLINK_DURATION) as u8;
+
+        // Lazy render
+        if blink != last_blink {
+            let banner = match (initial_pause, blink) {
+                (_, 3) => Banner::Moves(moves),
+                (true, 0) => Banner::Begin,
+                (true, 1) => Banner::Cancel,
+                (true, _) => Banner::Players,
+                (false, 0) => Banner::Paused,
+                (false, 1) => Banner::Restart,
+                (false, _) => Banner::Resume,
+            };
+            display
+                .draw_banner(&banner)
+                .map_err(|_| RuntimeError::DisplayError)?;
+            last_blink = blink;
+        } else {
+            time.delay_ms(LOOP_DELAY);
+        }
+
+        if serial_handler.keep_alive(time) {
+            serial_handler.send_game(
+                control,
+                (p1_time.into_millis(), p2_time.into_millis()),
+                moves,
+                phases,
+                match turn {
+                    Some(turn) => GameState::Paused(turn.clone()),
+                    None => GameState::Waiting,
+                },
+            );
+        }
+
+        // What the other side wants done, if it gets a say. When it runs the
+        // game, the players' buttons don't, and its times are taken as given
+        let slave = serial_handler.role() == Some(Role::Slave);
+        match serial_handler.read_command() {
+            Some(SerialMsg::Sync {
+                p1_time: p1_ms,
+                p2_time: p2_ms,
+            }) => {
+                *p1_time = TimeSetting::from_millis(p1_ms);
+                *p2_time = TimeSetting::from_millis(p2_ms);
+                if initial_pause {
+                    display
+                        .set_start_times(p1_time, p2_time)
+                        .map_err(|_| RuntimeError::DisplayError)?;
+                }
+                display
+                    .draw_times(p1_time, p2_time, phases)
+                    .map_err(|_| RuntimeError::DisplayError)?;
+                // The banner needs drawing again on top
+                last_blink = u8::MAX;
+                serial_handler.write(SerialMsg::Sync {
+                    p1_time: p1_ms,
+                    p2_time: p2_ms,
+                });
+            }
+            Some(SerialMsg::StartP1 {
+                p2_time: p2_ms,
+                move_number,
+            }) => {
+                if slave {
+                    *p2_time = TimeSetting::from_millis(p2_ms);
+                    moves.0 = move_number.saturating_sub(1) as u16;
+                }
+                break PauseResult::ResumedP1;
+            }
+            Some(SerialMsg::StartP2 {
+                p1_time: p1_ms,
+                move_number,
+            }) => {
+                if slave {
+                    *p1_time = TimeSetting::from_millis(p1_ms);
+                    moves.1 = move_number.saturating_sub(1) as u16;
+                }
+                break PauseResult::ResumedP2;
+            }
+            // Pausing again changes nothing
+            _ => (),
+        }
+
+        // Respond to input
+        if start.update(
+            start_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Falling)
+        {
+            // Start button released; reset and prompt for new time
+            break PauseResult::Stopped;
+        }
+        if down.update(
+            down_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Rising)
+            && !slave
+        {
+            // Down/P1 press; exit to P1 countdown
+            break PauseResult::ResumedP1;
+        }
+        if up.update(
+            up_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Rising)
+            && !slave
+        {
+            // Up/P2 press; exit to P2 countdown
+            break PauseResult::ResumedP2;
+        }
+    })
+}
@@ -0,0 +1,65 @@
+use crate::{
+    countdown::{Overtime, OvertimeMode, TimeControl},
+    time_set::{Increment, TimeSetting},
+};
+
+/// The number of presets, not counting "Custom".
+pub const PRESET_COUNT: usize = 9;
+
+/// A ready-made time control which can be picked on the set-up screen.
+pub struct Preset {
+    /// Padded to fill a whole line of the LCD
+    pub name: &'static str,
+    /// The starting time of both players
+    pub time: TimeSetting,
+    pub control: TimeControl,
+}
+
+/// Gets the preset at `index`, or `None` for "Custom" (or past the end).
+///
+/// The presets are built here rather than kept in a table so they don't take
+/// up any RAM.
+pub fn preset(index: usize) -> Option<Preset> {
+    Some(match index {
+        0 => fischer("   Bullet 1+0   ", 1, 0),
+        1 => fischer("   Blitz 3+2    ", 3, 2),
+        2 => fischer("   Blitz 5+0    ", 5, 0),
+        3 => fischer("   Rapid 10+5   ", 10, 5),
+        4 => fischer("  Rapid 15+10   ", 15, 10),
+        5 => fischer("Classical 90+30 ", 90, 30),
+        6 => byo_yomi("  Go 10m 5x30s  ", 10, 5, 30),
+        7 => byo_yomi("  Go 30m 5x30s  ", 30, 5, 30),
+        8 => byo_yomi("  Go 60m 5x60s  ", 60, 5, 60),
+        _ => return None,
+    })
+}
+
+/// A preset with `mins` minutes each and a Fischer increment of `increment`
+/// seconds.
+fn fischer(name: &'static str, mins: u16, increment: u8) -> Preset {
+    Preset {
+        name,
+        time: TimeSetting::new(mins * 60),
+        control: TimeControl {
+            increments: (Increment::new(increment), Increment::new(increment)),
+            ..TimeControl::new()
+        },
+    }
+}
+
+/// A preset with `mins` minutes each followed by `periods` byo-yomi periods of
+/// `secs` seconds.
+fn byo_yomi(name: &'static str, mins: u16, periods: u8, secs: u16) -> Preset {
+    Preset {
+        name,
+        time: TimeSetting::new(mins * 60),
+        control: TimeControl {
+            overtime: Overtime {
+                mode: OvertimeMode::ByoYomi,
+                count: periods,
+                period: TimeSetting::new(secs),
+            },
+            ..TimeControl::new()
+        },
+    }
+}
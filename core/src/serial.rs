@@ -0,0 +1,1216 @@
+//! Messages between the clock and the website, each sent as a frame which a
+//! lost or garbled byte can only spoil by itself. See /PROTOCOL.md for the
+//! details.
+
+use crate::{
+    countdown::{Phase, TimeControl, Turn},
+    hal::{SerialPort, TimeSource},
+};
+
+/// Ends every frame. COBS makes sure it's never found inside one.
+const DELIMITER: u8 = 0x00;
+/// The longest frame sent before COBS: a message type, four arguments and the
+/// CRC.
+const MAX_FRAME: usize = 1 + 4 * 4 + 2;
+/// The longest frame received before COBS, with room for newer versions of the
+/// protocol to add up to four more arguments to any message.
+const MAX_RECEIVED_FRAME: usize = MAX_FRAME + 4 * 4;
+
+/// The version of /PROTOCOL.md spoken here. Version 1 was unframed.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Sends `Increment` and `Mode` along with the times.
+pub const CAPABILITY_INCREMENTS: u32 = 1 << 0;
+/// Counts moves, so the move numbers in `StartP1` and `StartP2` mean something.
+pub const CAPABILITY_MOVE_COUNTERS: u32 = 1 << 1;
+/// Sends `StageP1` and `StageP2` for time controls with several stages.
+pub const CAPABILITY_STAGES: u32 = 1 << 2;
+/// Follows the `StartP1`, `StartP2`, `Pause` and `Sync` it receives.
+pub const CAPABILITY_REMOTE_CONTROL: u32 = 1 << 3;
+/// Sends `Heartbeat`s and drops the connection when they stop coming back,
+/// and sends `Phases` and `State` so a game can be picked up partway through.
+pub const CAPABILITY_RECONNECTS: u32 = 1 << 4;
+
+/// Everything the clock can do.
+const CAPABILITIES: u32 = CAPABILITY_INCREMENTS
+    | CAPABILITY_MOVE_COUNTERS
+    | CAPABILITY_STAGES
+    | CAPABILITY_REMOTE_CONTROL
+    | CAPABILITY_RECONNECTS;
+
+/// How often a `Heartbeat` is sent while connected, or a `Handshake` while
+/// not, in ms.
+const HEARTBEAT_MS: u32 = 1000;
+/// How long without hearing anything before the other side counts as gone,
+/// in ms.
+const DISCONNECT_MS: u32 = 3 * HEARTBEAT_MS;
+
+/// Who runs the game, as agreed in the handshake.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    /// The clock runs the game, and the other side follows it.
+    Master,
+    /// The other side runs the game, and the clock follows it.
+    Slave,
+    /// Either side can run the game, and the clock settles any conflicts.
+    Sync,
+}
+
+/// Where the game has got to, as sent in a `State`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum GameState {
+    /// Nobody has started their clock yet.
+    Waiting,
+    /// The player's clock is running.
+    Running(Turn),
+    /// The game is paused with the player to move.
+    Paused(Turn),
+    /// The player ran out of time.
+    Finished(Turn),
+}
+
+impl GameState {
+    fn to_u32(&self) -> u32 {
+        match self {
+            GameState::Waiting => 0x0000,
+            GameState::Running(Turn::P1) => 0x0001,
+            GameState::Running(Turn::P2) => 0x0002,
+            GameState::Paused(Turn::P1) => 0x0003,
+            GameState::Paused(Turn::P2) => 0x0004,
+            GameState::Finished(Turn::P1) => 0x0005,
+            GameState::Finished(Turn::P2) => 0x0006,
+        }
+    }
+}
+
+/// Who's at one end of the connection, as they say in their handshake.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Identity {
+    /// The newest version of the protocol they speak in a `Handshake`, or the
+    /// one they picked for both sides in a `HandshakeResponse`
+    pub protocol_version: u32,
+    /// The `CAPABILITY_` bits of what they can do
+    pub capabilities: u32,
+    /// Their software version as `0xMMmmpp`, or 0 if they don't have one
+    pub version: u32,
+}
+
+impl Identity {
+    /// The clock, running software `version` such as `1.2.0`.
+    pub fn clock(version: &str) -> Self {
+        Identity {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CAPABILITIES,
+            version: version_number(version),
+        }
+    }
+
+    /// Whether they can do everything in `capabilities`.
+    pub fn can(&self, capabilities: u32) -> bool {
+        self.capabilities & capabilities == capabilities
+    }
+}
+
+/// Packs `major.minor.patch` into `0xMMmmpp`, or gives 0 if `version` isn't
+/// like that.
+fn version_number(version: &str) -> u32 {
+    let mut parts = version.split('.').map(|part| part.parse::<u8>().ok());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => {
+            (major as u32) << 16 | (minor as u32) << 8 | patch as u32
+        }
+        _ => 0,
+    }
+}
+
+/// The types of sendable messages.
+/// Keep up to date with /www/src/serial.ts and /PROTOCOL.md
+///
+/// Note: all arguments are big-endian
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SerialMsg {
+    /// Just send HandshakeResponse back if you get this.
+    ///
+    /// Parameter: mode
+    ///   0x0000 - let the other party decide
+    ///   0x0001 - sync
+    ///   0x0002 - other party is slave
+    ///   0x0003 - other party is master
+    ///
+    /// Followed by the sender's protocol version, capabilities and software
+    /// version.
+    ///
+    /// 0xc0
+    Handshake { mode: u32, sender: Identity },
+    /// Yay, Handshake was successful!
+    ///
+    /// Parameter: selected_mode
+    ///   0x0000 - mode not supported
+    ///   0x0001 - sync
+    ///   0x0002 - we're slave
+    ///   0x0003 - we're master
+    ///
+    /// Followed by the protocol version both sides are to speak, which is the
+    /// older of the two, and the sender's capabilities and software version.
+    ///
+    /// 0xc1
+    HandshakeResponse {
+        selected_mode: u32,
+        sender: Identity,
+    },
+    /// P1 is now counting down on the specified move (counting from 1), and P2
+    /// has the specified # of ms.
+    ///
+    /// 0xc2
+    StartP1 { p2_time: u32, move_number: u32 },
+    /// P2 is now counting down on the specified move (counting from 1), and P1
+    /// has the specified # of ms.
+    ///
+    /// 0xc3
+    StartP2 { p1_time: u32, move_number: u32 },
+    /// Syncing time. P1 first, then P2, in ms.
+    ///
+    /// 0xc4
+    Sync { p1_time: u32, p2_time: u32 },
+    /// Pause both times. The currently running clock finished at the specified
+    /// ms.
+    ///
+    /// 0xc5
+    Pause { time: u32 },
+    /// P1 won by time.
+    ///
+    /// 0xc6
+    P1Finish,
+    /// P2 won by time.
+    ///
+    /// 0xc7
+    P2Finish,
+    /// The increment or delay given for every move. P1 first, then P2, in ms.
+    ///
+    /// 0xc8
+    Increment {
+        p1_increment: u32,
+        p2_increment: u32,
+    },
+    /// What the increment means.
+    ///
+    /// Parameter: mode
+    ///   0x0000 - Fischer increment
+    ///   0x0001 - Bronstein delay
+    ///   0x0002 - simple delay
+    ///   0x0003 - hourglass (no increment; the waiting player gains the time
+    ///            the running player uses)
+    ///
+    /// 0xc9
+    Mode { mode: u32 },
+    /// P1 reached the specified stage of the time control, counting from 1.
+    ///
+    /// 0xca
+    StageP1 { stage: u32 },
+    /// P2 reached the specified stage of the time control, counting from 1.
+    ///
+    /// 0xcb
+    StageP2 { stage: u32 },
+    /// Still here. Sent every second by both sides once they're connected, if
+    /// they both can.
+    ///
+    /// 0xcc
+    Heartbeat,
+    /// Which part of the time control P1 and then P2 are on, and how much of
+    /// it they have left.
+    ///
+    /// Parameters: p1_phase, p1_left, p2_phase, p2_left
+    ///   0x0000 - main time, with 0 left
+    ///   0x0001 - byo-yomi, with the periods left including the current one
+    ///   0x0002 - Canadian overtime, with the moves left in the current block
+    ///
+    /// 0xcd
+    Phases {
+        p1_phase: u32,
+        p1_left: u32,
+        p2_phase: u32,
+        p2_left: u32,
+    },
+    /// Where the game has got to, and how many moves P1 and then P2 have
+    /// made. Sent last in the whole game for someone who's just connected,
+    /// after the times and everything else.
+    ///
+    /// Parameter: state
+    ///   0x0000 - nobody has started yet
+    ///   0x0001 - P1's clock is running
+    ///   0x0002 - P2's clock is running
+    ///   0x0003 - paused with P1 to move
+    ///   0x0004 - paused with P2 to move
+    ///   0x0005 - P1 ran out of time
+    ///   0x0006 - P2 ran out of time
+    ///
+    /// 0xce
+    State {
+        state: u32,
+        p1_moves: u32,
+        p2_moves: u32,
+    },
+}
+
+impl SerialMsg {
+    fn to_u8(self) -> u8 {
+        match self {
+            SerialMsg::Handshake { mode: _, sender: _ } => 0xc0,
+            SerialMsg::HandshakeResponse {
+                selected_mode: _,
+                sender: _,
+            } => 0xc1,
+            SerialMsg::StartP1 {
+                p2_time: _,
+                move_number: _,
+            } => 0xc2,
+            SerialMsg::StartP2 {
+                p1_time: _,
+                move_number: _,
+            } => 0xc3,
+            SerialMsg::Sync {
+                p1_time: _,
+                p2_time: _,
+            } => 0xc4,
+            SerialMsg::Pause { time: _ } => 0xc5,
+            SerialMsg::P1Finish => 0xc6,
+            SerialMsg::P2Finish => 0xc7,
+            SerialMsg::Increment {
+                p1_increment: _,
+                p2_increment: _,
+            } => 0xc8,
+            SerialMsg::Mode { mode: _ } => 0xc9,
+            SerialMsg::StageP1 { stage: _ } => 0xca,
+            SerialMsg::StageP2 { stage: _ } => 0xcb,
+            SerialMsg::Heartbeat => 0xcc,
+            SerialMsg::Phases {
+                p1_phase: _,
+                p1_left: _,
+                p2_phase: _,
+                p2_left: _,
+            } => 0xcd,
+            SerialMsg::State {
+                state: _,
+                p1_moves: _,
+                p2_moves: _,
+            } => 0xce,
+        }
+    }
+
+    /// The message's arguments in the order they're sent, and how many of
+    /// them there are.
+    fn arguments(&self) -> ([u32; 4], usize) {
+        match *self {
+            SerialMsg::Handshake { mode, sender } => (
+                [
+                    mode,
+                    sender.protocol_version,
+                    sender.capabilities,
+                    sender.version,
+                ],
+                4,
+            ),
+            SerialMsg::HandshakeResponse {
+                selected_mode,
+                sender,
+            } => (
+                [
+                    selected_mode,
+                    sender.protocol_version,
+                    sender.capabilities,
+                    sender.version,
+                ],
+                4,
+            ),
+            SerialMsg::StartP1 {
+                p2_time,
+                move_number,
+            } => ([p2_time, move_number, 0, 0], 2),
+            SerialMsg::StartP2 {
+                p1_time,
+                move_number,
+            } => ([p1_time, move_number, 0, 0], 2),
+            SerialMsg::Sync { p1_time, p2_time } => ([p1_time, p2_time, 0, 0], 2),
+            SerialMsg::Pause { time } => ([time, 0, 0, 0], 1),
+            SerialMsg::P1Finish => ([0; 4], 0),
+            SerialMsg::P2Finish => ([0; 4], 0),
+            SerialMsg::Increment {
+                p1_increment,
+                p2_increment,
+            } => ([p1_increment, p2_increment, 0, 0], 2),
+            SerialMsg::Mode { mode } => ([mode, 0, 0, 0], 1),
+            SerialMsg::StageP1 { stage } => ([stage, 0, 0, 0], 1),
+            SerialMsg::StageP2 { stage } => ([stage, 0, 0, 0], 1),
+            SerialMsg::Heartbeat => ([0; 4], 0),
+            SerialMsg::Phases {
+                p1_phase,
+                p1_left,
+                p2_phase,
+                p2_left,
+            } => ([p1_phase, p1_left, p2_phase, p2_left], 4),
+            SerialMsg::State {
+                state,
+                p1_moves,
+                p2_moves,
+            } => ([state, p1_moves, p2_moves, 0], 3),
+        }
+    }
+
+    /// What the other side has to be able to do to make sense of the message.
+    fn needs(&self) -> u32 {
+        match *self {
+            SerialMsg::Increment {
+                p1_increment: _,
+                p2_increment: _,
+            }
+            | SerialMsg::Mode { mode: _ } => CAPABILITY_INCREMENTS,
+            SerialMsg::StageP1 { stage: _ } | SerialMsg::StageP2 { stage: _ } => CAPABILITY_STAGES,
+            SerialMsg::Heartbeat
+            | SerialMsg::Phases {
+                p1_phase: _,
+                p1_left: _,
+                p2_phase: _,
+                p2_left: _,
+            }
+            | SerialMsg::State {
+                state: _,
+                p1_moves: _,
+                p2_moves: _,
+            } => CAPABILITY_RECONNECTS,
+            _ => 0,
+        }
+    }
+
+    /// Puts the message type and its arguments at the start of `frame`,
+    /// returning how many bytes they take.
+    fn write_to(&self, frame: &mut [u8; MAX_FRAME]) -> usize {
+        let (arguments, count) = self.arguments();
+        frame[0] = self.to_u8();
+        for (i, argument) in arguments[..count].iter().enumerate() {
+            frame[1 + i * 4..][..4].copy_from_slice(&argument.to_be_bytes());
+        }
+        1 + count * 4
+    }
+
+    /// Reads a message from the type and arguments in a frame, if it's one we
+    /// know with at least the arguments we know about.
+    fn from_frame(frame: &[u8]) -> Option<SerialMsg> {
+        let (&msg_type, arguments) = frame.split_first()?;
+        let argument = |i: usize| {
+            let bytes = arguments.get(i * 4..i * 4 + 4)?;
+            Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        };
+        let identity = || {
+            Some(Identity {
+                protocol_version: argument(1)?,
+                capabilities: argument(2)?,
+                version: argument(3)?,
+            })
+        };
+        let msg = match msg_type {
+            0xc0 => SerialMsg::Handshake {
+                mode: argument(0)?,
+                sender: identity()?,
+            },
+            0xc1 => SerialMsg::HandshakeResponse {
+                selected_mode: argument(0)?,
+                sender: identity()?,
+            },
+            0xc2 => SerialMsg::StartP1 {
+                p2_time: argument(0)?,
+                move_number: argument(1)?,
+            },
+            0xc3 => SerialMsg::StartP2 {
+                p1_time: argument(0)?,
+                move_number: argument(1)?,
+            },
+            0xc4 => SerialMsg::Sync {
+                p1_time: argument(0)?,
+                p2_time: argument(1)?,
+            },
+            0xc5 => SerialMsg::Pause { time: argument(0)? },
+            0xc6 => SerialMsg::P1Finish,
+            0xc7 => SerialMsg::P2Finish,
+            0xc8 => SerialMsg::Increment {
+                p1_increment: argument(0)?,
+                p2_increment: argument(1)?,
+            },
+            0xc9 => SerialMsg::Mode { mode: argument(0)? },
+            0xca => SerialMsg::StageP1 {
+                stage: argument(0)?,
+            },
+            0xcb => SerialMsg::StageP2 {
+                stage: argument(0)?,
+            },
+            0xcc => SerialMsg::Heartbeat,
+            0xcd => SerialMsg::Phases {
+                p1_phase: argument(0)?,
+                p1_left: argument(1)?,
+                p2_phase: argument(2)?,
+                p2_left: argument(3)?,
+            },
+            0xce => SerialMsg::State {
+                state: argument(0)?,
+                p1_moves: argument(1)?,
+                p2_moves: argument(2)?,
+            },
+            _ => return None,
+        };
+        // Newer versions of the protocol can add arguments on the end, which we
+        // ignore, but they're always whole
+        (arguments.len() % 4 == 0).then_some(msg)
+    }
+
+    /// Sends the message in a frame on `serial`, whether or not there's anyone
+    /// on the other end to understand it.
+    pub fn send<S: SerialPort>(&self, serial: &mut S) {
+        let mut frame = [0; MAX_FRAME];
+        let len = self.write_to(&mut frame);
+        let crc = crc16(&frame[..len]);
+        frame[len..len + 2].copy_from_slice(&crc.to_be_bytes());
+        write_frame(serial, &frame[..len + 2]);
+    }
+
+    /// Whether the message tells the clock to do something to the game.
+    fn is_command(&self) -> bool {
+        matches!(
+            *self,
+            SerialMsg::StartP1 {
+                p2_time: _,
+                move_number: _,
+            } | SerialMsg::StartP2 {
+                p1_time: _,
+                move_number: _,
+            } | SerialMsg::Pause { time: _ }
+                | SerialMsg::Sync {
+                    p1_time: _,
+                    p2_time: _,
+                }
+        )
+    }
+
+    fn is_connection_message(&self) -> bool {
+        matches!(
+            *self,
+            SerialMsg::Handshake { mode: _, sender: _ }
+                | SerialMsg::HandshakeResponse {
+                    selected_mode: _,
+                    sender: _,
+                }
+        )
+    }
+}
+
+/// CRC-16/CCITT-FALSE, with the polynomial 0x1021 starting from 0xffff.
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xffff, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// The type and arguments of a frame if its CRC, in its last two bytes, is
+/// right.
+fn check_crc(frame: &[u8]) -> Option<&[u8]> {
+    let (body, crc) = frame.split_at(frame.len().checked_sub(2)?);
+    (crc == crc16(body).to_be_bytes()).then_some(body)
+}
+
+/// Which part of the time control `phase` is, and how much of it is left, as
+/// sent in `Phases`.
+fn phase_arguments(phase: &Phase) -> (u32, u32) {
+    match *phase {
+        Phase::Main => (0x0000, 0),
+        Phase::ByoYomi { periods } => (0x0001, periods as u32),
+        Phase::Canadian { moves } => (0x0002, moves as u32),
+    }
+}
+
+/// Sends `frame` on `serial` with COBS, so there's no zero in it, and then a
+/// delimiter.
+fn write_frame<S: SerialPort>(serial: &mut S, frame: &[u8]) {
+    // Frames are much shorter than COBS's 254-byte limit, so every block ends
+    // at a zero or at the end of the frame
+    for block in frame.split(|&byte| byte == DELIMITER) {
+        serial.write_byte(block.len() as u8 + 1);
+        for &byte in block {
+            serial.write_byte(byte);
+        }
+    }
+    serial.write_byte(DELIMITER);
+}
+
+/// Undoes COBS a byte at a time, collecting a frame until its delimiter.
+struct FrameDecoder {
+    frame: [u8; MAX_RECEIVED_FRAME],
+    len: usize,
+    /// How many bytes are left in the current COBS block, which is 0 when the
+    /// next byte starts a new one.
+    remaining: u8,
+    /// Whether a zero goes between the current block and the next one.
+    zero_next: bool,
+    /// Whether the frame has outgrown every message, so it can only be junk.
+    too_long: bool,
+}
+
+impl FrameDecoder {
+    fn new() -> Self {
+        FrameDecoder {
+            frame: [0; MAX_RECEIVED_FRAME],
+            len: 0,
+            remaining: 0,
+            zero_next: false,
+            too_long: false,
+        }
+    }
+
+    /// Takes the next byte off the wire. If it's a delimiter, returns the
+    /// frame it ends unless that frame can't possibly be whole.
+    fn push(&mut self, byte: u8) -> Option<&[u8]> {
+        if byte == DELIMITER {
+            // A delimiter in the middle of a block means bytes were lost
+            let whole = self.remaining == 0 && !self.too_long;
+            let len = self.len;
+            self.len = 0;
+            self.remaining = 0;
+            self.zero_next = false;
+            self.too_long = false;
+            return whole.then_some(&self.frame[..len]);
+        }
+        if self.remaining == 0 {
+            if self.zero_next {
+                self.append(0);
+            }
+            // 0xff is the only code whose block isn't followed by a zero
+            self.remaining = byte - 1;
+            self.zero_next = byte != 0xff;
+        } else {
+            self.append(byte);
+            self.remaining -= 1;
+        }
+        None
+    }
+
+    fn append(&mut self, byte: u8) {
+        match self.frame.get_mut(self.len) {
+            Some(slot) => {
+                *slot = byte;
+                self.len += 1;
+            }
+            None => self.too_long = true,
+        }
+    }
+}
+
+pub struct SerialHandler<S: SerialPort> {
+    serial: S,
+    decoder: FrameDecoder,
+    wait_start: Option<u32>,
+    /// Who we are in handshakes
+    identity: Identity,
+    /// Who's on the other end, once the handshake is done
+    peer: Option<Identity>,
+    /// Who runs the game while there's someone on the other end
+    role: Role,
+    /// Whether anything has arrived since [`SerialHandler::keep_alive`] last
+    /// looked
+    heard: bool,
+    /// When something last arrived, in ms
+    last_heard: u32,
+    /// When the last `Heartbeat` or `Handshake` was sent, in ms
+    last_sent: u32,
+    /// Whether the other side has connected since
+    /// [`SerialHandler::keep_alive`] last looked
+    just_connected: bool,
+}
+
+/// Handles serial communication between the firmware and website.
+/// Keep up to date with /www/src/serial.ts and /PROTOCOL.md
+impl<S: SerialPort> SerialHandler<S> {
+    pub fn write(&mut self, msg: SerialMsg) {
+        // don't write to serial if not connected, or if they wouldn't
+        // understand it
+        let understood = self.peer.is_some_and(|peer| peer.can(msg.needs()));
+        if understood || msg.is_connection_message() {
+            msg.send(&mut self.serial);
+        }
+    }
+
+    /// Reads a raw message if a whole one has arrived, dropping any frames
+    /// which were garbled on the way.
+    fn raw_read(&mut self) -> Option<SerialMsg> {
+        while let Some(byte) = self.serial.read_byte() {
+            let msg = self
+                .decoder
+                .push(byte)
+                .and_then(check_crc)
+                .and_then(SerialMsg::from_frame);
+            if msg.is_some() {
+                self.heard = true;
+                return msg;
+            }
+        }
+        None
+    }
+
+    /// Reads a message if one has arrived, dealing with any handshakes itself.
+    pub fn read(&mut self) -> Option<SerialMsg> {
+        let msg = self.raw_read()?;
+        match msg {
+            SerialMsg::Handshake { mode, sender } => {
+                // Both sides speak the older protocol, and we don't speak
+                // anything older than ours
+                let protocol_version = sender.protocol_version.min(PROTOCOL_VERSION);
+                let (selected_mode, role) = match mode {
+                    _ if protocol_version != PROTOCOL_VERSION => (0x0000, None),
+                    0x0000 => (0x0003, Some(Role::Master)), // if we decide, make them a slave
+                    0x0001 => (0x0001, Some(Role::Sync)),   // syncing
+                    0x0002 => (0x0002, Some(Role::Slave)),  // we're slave
+                    0x0003 => (0x0003, Some(Role::Master)), // we're master
+                    _ => (0x0000, None),                    // unsupported value
+                };
+                self.peer = role.map(|_| Identity {
+                    protocol_version,
+                    ..sender
+                });
+                self.role = role.unwrap_or(Role::Master);
+                self.just_connected = self.peer.is_some();
+                self.write(SerialMsg::HandshakeResponse {
+                    selected_mode,
+                    sender: Identity {
+                        protocol_version,
+                        ..self.identity
+                    },
+                });
+                None
+            }
+            SerialMsg::HandshakeResponse {
+                selected_mode,
+                sender,
+            } => {
+                // They have to follow us, as we asked, in the only protocol we
+                // speak
+                if selected_mode == 0x0002 && sender.protocol_version == PROTOCOL_VERSION {
+                    self.peer = Some(sender);
+                    self.role = Role::Master;
+                    self.just_connected = true;
+                }
+                None
+            }
+            _ => Some(msg),
+        }
+    }
+
+    /// Reads a `StartP1`, `StartP2`, `Pause` or `Sync` for the clock to
+    /// follow, if the other side gets a say in the game. Anything else that
+    /// arrives is dropped.
+    pub fn read_command(&mut self) -> Option<SerialMsg> {
+        let msg = self.read()?;
+        let follows = matches!(self.role(), Some(Role::Slave | Role::Sync));
+        (follows && msg.is_command()).then_some(msg)
+    }
+
+    /// Who's on the other end, if anyone.
+    pub fn peer(&self) -> Option<&Identity> {
+        self.peer.as_ref()
+    }
+
+    /// Who runs the game, if there's anyone on the other end.
+    pub fn role(&self) -> Option<Role> {
+        self.peer.map(|_| self.role)
+    }
+
+    /// Sends a handshake from the clock running software `version` and waits
+    /// for the other side to answer. Returns `None` while still waiting, so
+    /// call it until it returns whether we're connected.
+    pub fn check_connection<T: TimeSource>(
+        &mut self,
+        timeout_ms: u16,
+        version: &str,
+        time: &T,
+    ) -> Option<bool> {
+        if self.wait_start.is_none() {
+            self.peer = None;
+            self.identity = Identity::clock(version);
+            self.wait_start = Some(time.millis());
+            // End whatever the other side was halfway through receiving, so
+            // the handshake gets there in one piece
+            self.serial.write_byte(DELIMITER);
+            self.write(SerialMsg::Handshake {
+                mode: 0x0002,
+                sender: self.identity,
+            });
+        }
+        let wait_start = self.wait_start.unwrap();
+        // Anything but a handshake is from a side which hasn't answered ours,
+        // like a website still connected from before the clock restarted. It
+        // can't be followed until it has, so it's dropped rather than let it
+        // hold up the answer behind it or the timeout. Such a side still hears
+        // the handshakes sent while not connected, and the whole game once it
+        // answers one
+        while self.peer.is_none() && self.read().is_some() {}
+        if self.peer.is_some() {
+            Some(true)
+        } else if (time.millis() - wait_start) >= timeout_ms.into() {
+            Some(false)
+        } else {
+            // ignore. still waiting.
+            None
+        }
+    }
+
+    /// Keeps the connection going, so call it every time round the loop. Sends
+    /// a `Heartbeat` every second while connected, and drops the connection
+    /// if nothing has arrived for three. While not connected, sends a
+    /// `Handshake` every second instead, so whoever turns up can answer it.
+    ///
+    /// Returns whether the other side has just connected, in which case it
+    /// needs the whole game sending.
+    pub fn keep_alive<T: TimeSource>(&mut self, time: &T) -> bool {
+        let now = time.millis();
+        if core::mem::take(&mut self.heard) {
+            self.last_heard = now;
+        }
+        match self.peer {
+            // Older versions don't send heartbeats, so they can't be missed
+            Some(peer) if !peer.can(CAPABILITY_RECONNECTS) => (),
+            Some(_) if now.wrapping_sub(self.last_heard) >= DISCONNECT_MS => self.peer = None,
+            _ if now.wrapping_sub(self.last_sent) < HEARTBEAT_MS => (),
+            Some(_) => {
+                self.write(SerialMsg::Heartbeat);
+                self.last_sent = now;
+            }
+            None => {
+                self.serial.write_byte(DELIMITER);
+                self.write(SerialMsg::Handshake {
+                    mode: 0x0002,
+                    sender: self.identity,
+                });
+                self.last_sent = now;
+            }
+        }
+        core::mem::take(&mut self.just_connected)
+    }
+
+    /// Sends the whole game as it stands with the players on `times` in ms,
+    /// for someone who's just connected partway through it.
+    pub fn send_game(
+        &mut self,
+        control: &TimeControl,
+        times: (u32, u32),
+        moves: &(u16, u16),
+        phases: &(Phase, Phase),
+        state: GameState,
+    ) {
+        let increments = control.increments(moves);
+        self.write(SerialMsg::Mode {
+            mode: control.mode as u32,
+        });
+        self.write(SerialMsg::Increment {
+            p1_increment: increments.0.into_millis(),
+            p2_increment: increments.1.into_millis(),
+        });
+        self.write(SerialMsg::Sync {
+            p1_time: times.0,
+            p2_time: times.1,
+        });
+        self.write(SerialMsg::StageP1 {
+            stage: control.stage(moves.0) as u32 + 1,
+        });
+        self.write(SerialMsg::StageP2 {
+            stage: control.stage(moves.1) as u32 + 1,
+        });
+        self.send_phases(phases);
+        self.write(SerialMsg::State {
+            state: state.to_u32(),
+            p1_moves: moves.0 as u32,
+            p2_moves: moves.1 as u32,
+        });
+    }
+
+    /// Sends which part of the time control each player is in.
+    pub fn send_phases(&mut self, phases: &(Phase, Phase)) {
+        let (p1_phase, p1_left) = phase_arguments(&phases.0);
+        let (p2_phase, p2_left) = phase_arguments(&phases.1);
+        self.write(SerialMsg::Phases {
+            p1_phase,
+            p1_left,
+            p2_phase,
+            p2_left,
+        });
+    }
+
+    /// Sends the times being set in ms, which the players will start from.
+    pub fn send_setting(&mut self, times: (u32, u32)) {
+        self.write(SerialMsg::Sync {
+            p1_time: times.0,
+            p2_time: times.1,
+        });
+        self.write(SerialMsg::State {
+            state: GameState::Waiting.to_u32(),
+            p1_moves: 0,
+            p2_moves: 0,
+        });
+    }
+
+    pub fn new(serial: S) -> Self {
+        Self {
+            serial,
+            decoder: FrameDecoder::new(),
+            wait_start: None,
+            identity: Identity::clock(""),
+            peer: None,
+            role: Role::Master,
+            heard: false,
+            last_heard: 0,
+            last_sent: 0,
+            just_connected: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both ends of a serial cable, looped back on itself.
+    struct Wire {
+        bytes: [u8; 128],
+        written: usize,
+        read: usize,
+    }
+
+    impl SerialPort for Wire {
+        fn read_byte(&mut self) -> Option<u8> {
+            let byte = self.bytes[..self.written].get(self.read).copied();
+            self.read += byte.is_some() as usize;
+            byte
+        }
+
+        fn write_byte(&mut self, byte: u8) {
+            self.bytes[self.written] = byte;
+            self.written += 1;
+        }
+    }
+
+    /// A clock which is always at 0ms.
+    struct Stopped;
+
+    impl TimeSource for Stopped {
+        fn millis(&self) -> u32 {
+            0
+        }
+
+        fn delay_ms(&mut self, _ms: u16) {}
+    }
+
+    /// A clock which is stuck at the given ms.
+    struct At(u32);
+
+    impl TimeSource for At {
+        fn millis(&self) -> u32 {
+            self.0
+        }
+
+        fn delay_ms(&mut self, _ms: u16) {}
+    }
+
+    fn unplugged() -> SerialHandler<Wire> {
+        SerialHandler::new(Wire {
+            bytes: [0; 128],
+            written: 0,
+            read: 0,
+        })
+    }
+
+    fn connected() -> SerialHandler<Wire> {
+        let mut handler = unplugged();
+        handler.peer = Some(Identity::clock("1.2.0"));
+        handler
+    }
+
+    /// Has the clock send its handshake, which gets `response`.
+    fn answered(response: SerialMsg) -> SerialHandler<Wire> {
+        let mut handler = unplugged();
+        // The handshake is for the other side to read, so nothing comes back
+        // until it's been sent
+        handler.serial.read = usize::MAX;
+        assert_eq!(handler.check_connection(500, "1.2.0", &Stopped), None);
+        handler.serial.read = handler.serial.written;
+        response.send(&mut handler.serial);
+        handler
+    }
+
+    #[test]
+    fn crc_matches_the_standard() {
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn frames_have_no_zeroes_but_the_delimiter() {
+        let mut handler = connected();
+        handler.write(SerialMsg::Pause { time: 0 });
+        let wire = &handler.serial;
+        assert_eq!(wire.bytes[wire.written - 1], DELIMITER);
+        assert!(!wire.bytes[..wire.written - 1].contains(&DELIMITER));
+        assert_eq!(handler.read(), Some(SerialMsg::Pause { time: 0 }));
+    }
+
+    #[test]
+    fn reads_back_what_was_written() {
+        let msgs = [
+            SerialMsg::StartP1 {
+                p2_time: 180_000,
+                move_number: 1,
+            },
+            SerialMsg::Sync {
+                p1_time: 0x00ff_0000,
+                p2_time: 0,
+            },
+            SerialMsg::P2Finish,
+            SerialMsg::Mode { mode: 3 },
+        ];
+        let mut handler = connected();
+        for msg in msgs {
+            handler.write(msg);
+        }
+        for msg in msgs {
+            assert_eq!(handler.read(), Some(msg));
+        }
+        assert_eq!(handler.read(), None);
+    }
+
+    #[test]
+    fn a_lost_byte_only_loses_its_frame() {
+        let mut handler = connected();
+        handler.write(SerialMsg::Pause { time: 1_000 });
+        let first = handler.serial.written;
+        handler.write(SerialMsg::Pause { time: 2_000 });
+        handler.write(SerialMsg::Pause { time: 3_000 });
+        // Drop a byte from the middle of the second frame
+        let wire = &mut handler.serial;
+        wire.bytes.copy_within(first + 3..wire.written, first + 2);
+        wire.written -= 1;
+        assert_eq!(handler.read(), Some(SerialMsg::Pause { time: 1_000 }));
+        assert_eq!(handler.read(), Some(SerialMsg::Pause { time: 3_000 }));
+        assert_eq!(handler.read(), None);
+    }
+
+    #[test]
+    fn skips_noise_and_unknown_messages() {
+        let mut handler = connected();
+        for byte in [0xc5, 0x12, 0x00, 0xff, 0xff, 0x00] {
+            handler.serial.write_byte(byte);
+        }
+        // A frame with a good CRC but a type nobody's heard of
+        let crc = crc16(&[0xee]).to_be_bytes();
+        write_frame(&mut handler.serial, &[0xee, crc[0], crc[1]]);
+        handler.write(SerialMsg::P1Finish);
+        assert_eq!(handler.read(), Some(SerialMsg::P1Finish));
+    }
+
+    #[test]
+    fn connects_to_a_follower_in_our_protocol() {
+        let website = Identity {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CAPABILITY_INCREMENTS,
+            version: 0,
+        };
+        let mut handler = answered(SerialMsg::HandshakeResponse {
+            selected_mode: 0x0002,
+            sender: website,
+        });
+        assert_eq!(handler.check_connection(500, "1.2.0", &Stopped), Some(true));
+        assert_eq!(handler.peer(), Some(&website));
+        // It's only sent what it says it understands
+        let written = handler.serial.written;
+        handler.write(SerialMsg::StageP1 { stage: 2 });
+        assert_eq!(handler.serial.written, written);
+        handler.write(SerialMsg::Mode { mode: 1 });
+        assert_ne!(handler.serial.written, written);
+    }
+
+    #[test]
+    fn drops_messages_from_before_the_handshake() {
+        let website = Identity {
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: CAPABILITY_INCREMENTS,
+            version: 0,
+        };
+        // Still following the game from before the clock restarted
+        let mut handler = answered(SerialMsg::Pause { time: 1_000 });
+        SerialMsg::Heartbeat.send(&mut handler.serial);
+        SerialMsg::HandshakeResponse {
+            selected_mode: 0x0002,
+            sender: website,
+        }
+        .send(&mut handler.serial);
+        assert_eq!(handler.check_connection(500, "1.2.0", &Stopped), Some(true));
+        assert_eq!(handler.peer(), Some(&website));
+        assert_eq!(handler.read(), None);
+    }
+
+    #[test]
+    fn times_out_on_a_side_which_never_answers() {
+        let mut handler = answered(SerialMsg::Pause { time: 1_000 });
+        assert_eq!(handler.check_connection(0, "1.2.0", &Stopped), Some(false));
+        assert_eq!(handler.peer(), None);
+    }
+
+    #[test]
+    fn refuses_other_modes_and_protocols() {
+        for (selected_mode, protocol_version) in [(0x0000, 2), (0x0003, 2), (0x0002, 3)] {
+            let mut handler = answered(SerialMsg::HandshakeResponse {
+                selected_mode,
+                sender: Identity {
+                    protocol_version,
+                    capabilities: 0,
+                    version: 0,
+                },
+            });
+            assert_eq!(handler.check_connection(0, "1.2.0", &Stopped), Some(false));
+            assert_eq!(handler.peer(), None);
+        }
+    }
+
+    #[test]
+    fn answers_newer_versions_in_our_protocol() {
+        let mut handler = unplugged();
+        let website = Identity {
+            protocol_version: PROTOCOL_VERSION + 1,
+            capabilities: CAPABILITY_INCREMENTS | 1 << 31,
+            version: 0x02_00_00,
+        };
+        handler.write(SerialMsg::Handshake {
+            mode: 0x0003,
+            sender: website,
+        });
+        assert_eq!(handler.read(), None);
+        assert_eq!(
+            handler.raw_read(),
+            Some(SerialMsg::HandshakeResponse {
+                selected_mode: 0x0003,
+                sender: Identity::clock(""),
+            })
+        );
+        assert_eq!(
+            handler.peer(),
+            Some(&Identity {
+                protocol_version: PROTOCOL_VERSION,
+                ..website
+            })
+        );
+    }
+
+    #[test]
+    fn takes_commands_only_from_a_host_with_a_say() {
+        for (mode, role, follows) in [
+            (0x0001, Role::Sync, true),
+            (0x0002, Role::Slave, true),
+            (0x0003, Role::Master, false),
+        ] {
+            let mut handler = unplugged();
+            handler.write(SerialMsg::Handshake {
+                mode,
+                sender: Identity::clock(""),
+            });
+            assert_eq!(handler.read_command(), None);
+            assert_eq!(handler.role(), Some(role));
+            // The answer is for the other side to read
+            handler.serial.read = handler.serial.written;
+            handler.write(SerialMsg::Pause { time: 1_000 });
+            handler.write(SerialMsg::P1Finish);
+            assert_eq!(
+                handler.read_command(),
+                follows.then_some(SerialMsg::Pause { time: 1_000 })
+            );
+            assert_eq!(handler.read_command(), None);
+        }
+    }
+
+    #[test]
+    fn drops_a_quiet_peer_and_asks_again() {
+        let mut handler = answered(SerialMsg::HandshakeResponse {
+            selected_mode: 0x0002,
+            sender: Identity {
+                protocol_version: PROTOCOL_VERSION,
+                capabilities: CAPABILITY_RECONNECTS,
+                version: 0,
+            },
+        });
+        assert_eq!(handler.check_connection(500, "1.2.0", &Stopped), Some(true));
+        assert!(handler.keep_alive(&At(0)));
+        // Heartbeats are for the other side to read
+        handler.serial.read = usize::MAX;
+        let written = handler.serial.written;
+        assert!(!handler.keep_alive(&At(1_000)));
+        assert_ne!(handler.serial.written, written);
+        assert!(handler.peer().is_some());
+        assert!(!handler.keep_alive(&At(3_000)));
+        assert_eq!(handler.peer(), None);
+        handler.serial.read = handler.serial.written;
+        assert!(!handler.keep_alive(&At(4_000)));
+        assert_eq!(
+            handler.raw_read(),
+            Some(SerialMsg::Handshake {
+                mode: 0x0002,
+                sender: Identity::clock("1.2.0"),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_arguments_from_newer_versions() {
+        let mut handler = connected();
+        let mut frame = [0xc5, 0, 0, 0x03, 0xe8, 0xde, 0xad, 0xbe, 0xef, 0, 0];
+        let crc = crc16(&frame[..9]).to_be_bytes();
+        frame[9..].copy_from_slice(&crc);
+        write_frame(&mut handler.serial, &frame);
+        assert_eq!(handler.read(), Some(SerialMsg::Pause { time: 1_000 }));
+    }
+
+    #[test]
+    fn keeps_handshakes_with_arguments_from_newer_versions() {
+        let mut handler = unplugged();
+        let mut frame = [0; 1 + 5 * 4 + 2];
+        frame[0] = 0xc0;
+        let arguments = [
+            0x0002,
+            PROTOCOL_VERSION,
+            CAPABILITY_RECONNECTS,
+            0,
+            0xdead_beef,
+        ];
+        for (i, argument) in arguments.iter().enumerate() {
+            frame[1 + i * 4..][..4].copy_from_slice(&argument.to_be_bytes());
+        }
+        let crc = crc16(&frame[..21]).to_be_bytes();
+        frame[21..].copy_from_slice(&crc);
+        write_frame(&mut handler.serial, &frame);
+        assert_eq!(
+            handler.raw_read(),
+            Some(SerialMsg::Handshake {
+                mode: 0x0002,
+                sender: Identity {
+                    protocol_version: PROTOCOL_VERSION,
+                    capabilities: CAPABILITY_RECONNECTS,
+                    version: 0,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn packs_versions() {
+        assert_eq!(version_number("1.2.0"), 0x01_02_00);
+        assert_eq!(version_number("10.0.255"), 0x0a_00_ff);
+        assert_eq!(version_number("test"), 0);
+        assert_eq!(version_number("1.2"), 0);
+        assert_eq!(version_number("1.2.3.4"), 0);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    countdown::{Overtime, OvertimeMode, Stage, TimeControl, TimeControlMode, MAX_STAGES},
+    display::Layout,
+    hal::Eeprom,
+    time_set::{Increment, TimeSetting, MAX_BYO_YOMI_PERIOD, MAX_OVERTIME_COUNT, MAX_STAGE_MOVES},
+};
+
+/// Bumped whenever the layout of a record changes so old records are ignored.
+const RECORD_VERSION: u8 = 2;
+/// The version byte, the times, the time control and the checksum.
+const RECORD_LENGTH: usize = 1 + 8 + 1 + 2 + 6 * MAX_STAGES + 1 + 6 + 1;
+
+/// The slot holding the last time control which was used.
+pub const LAST_USED_SLOT: usize = 0;
+/// The number of slots for time controls the user has saved.
+pub const USER_SLOTS: usize = 3;
+/// All the slots, with the last used one first and the user's after it.
+pub const SLOT_COUNT: usize = 1 + USER_SLOTS;
+/// Where the countdown layout is kept, straight after the slots.
+const LAYOUT_OFFSET: usize = SLOT_COUNT * RECORD_LENGTH;
+
+/// Keeps time controls in the EEPROM so they survive a power cycle.
+///
+/// Each slot is a record made of a version byte, the data and a CRC-8 of both.
+/// Anything which doesn't match is treated as an empty slot. The countdown
+/// layout is a single byte after the slots.
+pub struct Storage<E: Eeprom> {
+    eeprom: E,
+}
+
+impl<E: Eeprom> Storage<E> {
+    pub fn new(eeprom: E) -> Self {
+        Storage { eeprom }
+    }
+
+    /// Reads the time control in `slot`, or `None` if it's empty or corrupt.
+    pub fn load(&self, slot: usize) -> Option<((TimeSetting, TimeSetting), TimeControl)> {
+        if slot >= SLOT_COUNT {
+            return None;
+        }
+        let offset = (slot * RECORD_LENGTH) as u16;
+        let mut record = [0; RECORD_LENGTH];
+        for (i, byte) in record.iter_mut().enumerate() {
+            *byte = self.eeprom.read_byte(offset + i as u16);
+        }
+        if record[0] != RECORD_VERSION
+            || crc8(&record[..RECORD_LENGTH - 1]) != record[RECORD_LENGTH - 1]
+        {
+            return None;
+        }
+        decode(&record[1..RECORD_LENGTH - 1])
+    }
+
+    /// Writes a time control to `slot`, skipping bytes which haven't changed
+    /// to save wear on the EEPROM.
+    pub fn save(&mut self, slot: usize, times: &(TimeSetting, TimeSetting), control: &TimeControl) {
+        if slot >= SLOT_COUNT {
+            return;
+        }
+        let mut record = [0; RECORD_LENGTH];
+        record[0] = RECORD_VERSION;
+        encode(times, control, &mut record[1..RECORD_LENGTH - 1]);
+        record[RECORD_LENGTH - 1] = crc8(&record[..RECORD_LENGTH - 1]);
+
+        let offset = (slot * RECORD_LENGTH) as u16;
+        for (i, byte) in record.iter().enumerate() {
+            if self.eeprom.read_byte(offset + i as u16) != *byte {
+                self.eeprom.write_byte(offset + i as u16, *byte);
+            }
+        }
+    }
+
+    /// Reads the countdown layout, which is the standard one on a fresh chip.
+    pub fn load_layout(&self) -> Layout {
+        match self.eeprom.read_byte(LAYOUT_OFFSET as u16) {
+            1 => Layout::BigDigits,
+            _ => Layout::Standard,
+        }
+    }
+
+    pub fn save_layout(&mut self, layout: Layout) {
+        let byte = match layout {
+            Layout::Standard => 0,
+            Layout::BigDigits => 1,
+        };
+        if self.eeprom.read_byte(LAYOUT_OFFSET as u16) != byte {
+            self.eeprom.write_byte(LAYOUT_OFFSET as u16, byte);
+        }
+    }
+}
+
+fn encode(times: &(TimeSetting, TimeSetting), control: &TimeControl, data: &mut [u8]) {
+    let mut i = 0;
+    let mut push = |bytes: &[u8]| {
+        data[i..i + bytes.len()].copy_from_slice(bytes);
+        i += bytes.len();
+    };
+    push(&times.0.into_millis().to_be_bytes());
+    push(&times.1.into_millis().to_be_bytes());
+    push(&[
+        control.mode as u8,
+        control.increments.0.into_secs(),
+        control.increments.1.into_secs(),
+    ]);
+    for stage in &control.stages {
+        push(&[stage.after_moves]);
+        push(&stage.time.into_millis().to_be_bytes());
+        push(&[stage.increment.into_secs()]);
+    }
+    push(&[
+        control.stage_count,
+        control.overtime.mode as u8,
+        control.overtime.count,
+    ]);
+    push(&control.overtime.period.into_millis().to_be_bytes());
+}
+
+/// The reverse of [`encode`], checking that every value is one the set-up
+/// screen could have made.
+fn decode(data: &[u8]) -> Option<((TimeSetting, TimeSetting), TimeControl)> {
+    let time = |i: usize| {
+        let millis = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        (millis <= TimeSetting::MAX_TIME as u32 * 1000).then(|| TimeSetting::from_millis(millis))
+    };
+    let increment =
+        |i: usize| (data[i] <= Increment::MAX_INCREMENT).then(|| Increment::new(data[i]));
+
+    let times = (time(0)?, time(4)?);
+    let mode = match data[8] {
+        0 => TimeControlMode::Fischer,
+        1 => TimeControlMode::Bronstein,
+        2 => TimeControlMode::SimpleDelay,
+        3 => TimeControlMode::Hourglass,
+        _ => return None,
+    };
+    let increments = (increment(9)?, increment(10)?);
+    let mut stages = [Stage::new(); MAX_STAGES];
+    let mut i = 11;
+    for stage in stages.iter_mut() {
+        if data[i] > MAX_STAGE_MOVES {
+            return None;
+        }
+        *stage = Stage {
+            after_moves: data[i],
+            time: time(i + 1)?,
+            increment: increment(i + 5)?,
+        };
+        i += 6;
+    }
+    // The stages in use are the ones before the first which is never reached
+    let stage_count = data[i];
+    if stage_count as usize
+        != stages
+            .iter()
+            .take_while(|stage| stage.after_moves != 0)
+            .count()
+    {
+        return None;
+    }
+    let overtime = Overtime {
+        mode: match data[i + 1] {
+            0 => OvertimeMode::None,
+            1 => OvertimeMode::ByoYomi,
+            2 => OvertimeMode::Canadian,
+            _ => return None,
+        },
+        count: data[i + 2],
+        period: time(i + 3)?,
+    };
+    if !(1..=MAX_OVERTIME_COUNT).contains(&overtime.count) {
+        return None;
+    }
+    // Byo-yomi periods are set in whole seconds, up to a limit
+    let period_ms = overtime.period.into_millis();
+    if overtime.mode == OvertimeMode::ByoYomi
+        && (period_ms % 1000 != 0
+            || !(1..=MAX_BYO_YOMI_PERIOD as u32).contains(&(period_ms / 1000)))
+    {
+        return None;
+    }
+    // An hourglass has no increments, stages or overtime
+    if mode == TimeControlMode::Hourglass
+        && (increments != (Increment::new(0), Increment::new(0))
+            || stage_count != 0
+            || overtime.mode != OvertimeMode::None)
+    {
+        return None;
+    }
+
+    Some((
+        times,
+        TimeControl {
+            mode,
+            increments,
+            stages,
+            stage_count,
+            overtime,
+        },
+    ))
+}
+
+/// CRC-8 with the polynomial 0x07.
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc: u8 = 0;
+    for byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countdown::Stage;
+
+    /// Starts out erased, like a fresh chip.
+    struct FakeEeprom([u8; LAYOUT_OFFSET + 1]);
+
+    impl Eeprom for FakeEeprom {
+        fn read_byte(&self, offset: u16) -> u8 {
+            self.0[offset as usize]
+        }
+
+        fn write_byte(&mut self, offset: u16, byte: u8) {
+            self.0[offset as usize] = byte;
+        }
+    }
+
+    fn storage() -> Storage<FakeEeprom> {
+        Storage::new(FakeEeprom([0xff; LAYOUT_OFFSET + 1]))
+    }
+
+    fn control() -> TimeControl {
+        let mut stages = [Stage::new(); MAX_STAGES];
+        stages[0] = Stage {
+            after_moves: 40,
+            time: TimeSetting::new(30 * 60),
+            increment: Increment::new(30),
+        };
+        TimeControl {
+            mode: TimeControlMode::Bronstein,
+            increments: (Increment::new(30), Increment::new(10)),
+            stages,
+            stage_count: 1,
+            overtime: Overtime::new(OvertimeMode::Canadian),
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut storage = storage();
+        let times = (TimeSetting::new(90 * 60), TimeSetting::new(60 * 60));
+        storage.save(2, &times, &control());
+        assert_eq!(storage.load(2), Some((times, control())));
+        let hourglass = TimeControl {
+            mode: TimeControlMode::Hourglass,
+            ..TimeControl::new()
+        };
+        storage.save(3, &times, &hourglass);
+        assert_eq!(storage.load(3), Some((times, hourglass)));
+        // The other slots are untouched
+        assert_eq!(storage.load(LAST_USED_SLOT), None);
+        assert_eq!(storage.load(1), None);
+    }
+
+    #[test]
+    fn rejects_bad_records() {
+        let mut storage = storage();
+        let times = (TimeSetting::new(300), TimeSetting::new(300));
+        storage.save(LAST_USED_SLOT, &times, &control());
+
+        // A flipped bit fails the checksum
+        storage.eeprom.0[3] ^= 0x10;
+        assert_eq!(storage.load(LAST_USED_SLOT), None);
+        storage.eeprom.0[3] ^= 0x10;
+        assert!(storage.load(LAST_USED_SLOT).is_some());
+
+        // As does a record from another version, even with a good checksum
+        storage.eeprom.0[0] = RECORD_VERSION + 1;
+        storage.eeprom.0[RECORD_LENGTH - 1] = crc8(&storage.eeprom.0[..RECORD_LENGTH - 1]);
+        assert_eq!(storage.load(LAST_USED_SLOT), None);
+    }
+
+    #[test]
+    fn rejects_values_the_set_up_screen_cant_make() {
+        let times = (TimeSetting::new(300), TimeSetting::new(300));
+        let mut too_many_moves = control();
+        too_many_moves.stages[0].after_moves = 100;
+        let mut no_periods = control();
+        no_periods.overtime.count = 0;
+        let mut long_byo_yomi = control();
+        long_byo_yomi.overtime = Overtime::new(OvertimeMode::ByoYomi);
+        long_byo_yomi.overtime.period = TimeSetting::new(100);
+        let mut unreachable_stage = control();
+        unreachable_stage.stages[0].after_moves = 0;
+        let hourglass = TimeControl {
+            mode: TimeControlMode::Hourglass,
+            ..TimeControl::new()
+        };
+        let mut hourglass_increment = hourglass;
+        hourglass_increment.increments.1 = Increment::new(5);
+        let mut hourglass_stage = hourglass;
+        hourglass_stage.stages = control().stages;
+        hourglass_stage.stage_count = 1;
+        let mut hourglass_overtime = hourglass;
+        hourglass_overtime.overtime = Overtime::new(OvertimeMode::ByoYomi);
+        for control in [
+            too_many_moves,
+            no_periods,
+            long_byo_yomi,
+            unreachable_stage,
+            hourglass_increment,
+            hourglass_stage,
+            hourglass_overtime,
+        ] {
+            let mut storage = storage();
+            storage.save(LAST_USED_SLOT, &times, &control);
+            assert_eq!(storage.load(LAST_USED_SLOT), None);
+        }
+    }
+
+    #[test]
+    fn keeps_the_layout() {
+        let mut storage = storage();
+        assert_eq!(storage.load_layout(), Layout::Standard);
+        storage.save_layout(Layout::BigDigits);
+        assert_eq!(storage.load_layout(), Layout::BigDigits);
+        // Saving a time control leaves it alone
+        let times = (TimeSetting::new(300), TimeSetting::new(300));
+        storage.save(SLOT_COUNT - 1, &times, &control());
+        assert_eq!(storage.load_layout(), Layout::BigDigits);
+        storage.save_layout(Layout::Standard);
+        assert_eq!(storage.load_layout(), Layout::Standard);
+    }
+}
@@ -0,0 +1,736 @@
+use core::ops::{AddAssign, SubAssign};
+
+use debouncr::{debounce_4, Edge};
+
+use crate::{
+    countdown::{Overtime, OvertimeMode, Stage, TimeControl, TimeControlMode, MAX_STAGES},
+    display::{ClockDisplay, Setting},
+    error::RuntimeError,
+    hal::{Button, Eeprom, SerialPort, TimeSource},
+    presets::{preset, PRESET_COUNT},
+    serial::{SerialHandler, SerialMsg},
+    storage::{Storage, LAST_USED_SLOT, SLOT_COUNT, USER_SLOTS},
+};
+
+const BLINK_DURATION: u16 = 100;
+const HOLD_THRESHOLD: u16 = 150;
+const REPEAT_THRESHOLD: u16 = 20;
+const LOOP_DELAY: u16 = 5;
+pub(crate) const MAX_STAGE_MOVES: u8 = 99;
+pub(crate) const MAX_OVERTIME_COUNT: u8 = 99;
+pub(crate) const MAX_BYO_YOMI_PERIOD: u8 = 99;
+/// The preset menu lists the saved slots, then the built-in presets, then
+/// "Custom".
+const CUSTOM_ENTRY: usize = SLOT_COUNT + PRESET_COUNT;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TimeSetPart {
+    Preset,
+    P1SetMin,
+    P1SetSec,
+    P2SetMin,
+    P2SetSec,
+    SetMode,
+    P1SetInc,
+    P2SetInc,
+    StageMoves,
+    StageMin,
+    StageSec,
+    StageInc,
+    SetOvertime,
+    OvertimeCount,
+    OvertimeMin,
+    OvertimeSec,
+    SavePreset,
+}
+
+/// How a time is rounded to the whole seconds shown on the display.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Rounding {
+    /// A clock only shows `0:00:00` once it has really run out, as FIDE
+    /// expects.
+    Up,
+    /// A clock shows `0:00:00` for the last second before it runs out.
+    Down,
+    Nearest,
+}
+
+/// The rounding used for every time on the display. Only the display is
+/// affected; the clocks themselves always keep every millisecond.
+pub const DISPLAY_ROUNDING: Rounding = Rounding::Up;
+
+/// A time on a player's clock, kept to the millisecond so nothing is lost when
+/// the game is paused and resumed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TimeSetting(u32);
+
+impl TimeSetting {
+    /// The most seconds which can be set on the set-up screen.
+    pub const MAX_TIME: u16 = (60 * 60 * 10) - 1;
+
+    pub fn new(seconds: u16) -> TimeSetting {
+        TimeSetting(seconds as u32 * 1000)
+    }
+
+    pub fn from_millis(millis: u32) -> TimeSetting {
+        TimeSetting(millis)
+    }
+
+    /// The hours, minutes and seconds shown on the display, rounded with
+    /// [`DISPLAY_ROUNDING`].
+    pub fn into_hrs_mins_secs(&self) -> (u8, u8, u8) {
+        let secs = self.into_secs();
+        (
+            (secs / (60 * 60)).min(u8::MAX as u32) as u8,
+            ((secs / 60) % 60) as u8,
+            (secs % 60) as u8,
+        )
+    }
+
+    /// The whole seconds shown on the display, rounded with
+    /// [`DISPLAY_ROUNDING`].
+    pub fn into_secs(&self) -> u32 {
+        match DISPLAY_ROUNDING {
+            Rounding::Up => self.0 / 1000 + (self.0 % 1000 != 0) as u32,
+            Rounding::Down => self.0 / 1000,
+            Rounding::Nearest => self.0 / 1000 + (self.0 % 1000 >= 500) as u32,
+        }
+    }
+
+    /// The tenths of a second shown on displays which show them, rounded with
+    /// [`DISPLAY_ROUNDING`] like the whole seconds.
+    pub fn into_tenths(&self) -> u32 {
+        match DISPLAY_ROUNDING {
+            Rounding::Up => self.0 / 100 + (self.0 % 100 != 0) as u32,
+            Rounding::Down => self.0 / 100,
+            Rounding::Nearest => self.0 / 100 + (self.0 % 100 >= 50) as u32,
+        }
+    }
+
+    pub fn into_millis(&self) -> u32 {
+        self.0
+    }
+}
+
+impl AddAssign<u16> for TimeSetting {
+    /// Adds `rhs` seconds.
+    fn add_assign(&mut self, rhs: u16) {
+        // If too high, go back to zero
+        match self.0.saturating_add(rhs as u32 * 1000) {
+            r if r <= TimeSetting::MAX_TIME as u32 * 1000 => self.0 = r,
+            _ => self.0 = 0,
+        }
+    }
+}
+
+impl SubAssign<u16> for TimeSetting {
+    /// Subtracts `rhs` seconds.
+    fn sub_assign(&mut self, rhs: u16) {
+        // If underflow, wrap to highest value
+        match self.0.checked_sub(rhs as u32 * 1000) {
+            Some(r) => self.0 = r,
+            None => self.0 = TimeSetting::MAX_TIME as u32 * 1000,
+        }
+    }
+}
+
+/// The number of seconds of increment or delay a player gets for each of their
+/// moves, depending on the [`TimeControlMode`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Increment(u8);
+
+impl Increment {
+    pub const MAX_INCREMENT: u8 = 99;
+
+    pub fn new(seconds: u8) -> Increment {
+        Increment(seconds)
+    }
+
+    pub fn into_secs(&self) -> u8 {
+        self.0
+    }
+
+    pub fn into_millis(&self) -> u32 {
+        self.0 as u32 * 1000
+    }
+}
+
+impl AddAssign<u8> for Increment {
+    fn add_assign(&mut self, rhs: u8) {
+        // If overflow or too high, go back to zero
+        match self.0.checked_add(rhs) {
+            Some(r) if r <= Increment::MAX_INCREMENT => self.0 = r,
+            Some(_) | None => self.0 = 0,
+        }
+    }
+}
+
+impl SubAssign<u8> for Increment {
+    fn sub_assign(&mut self, rhs: u8) {
+        // If underflow, wrap to highest value
+        match self.0.checked_sub(rhs) {
+            Some(r) => self.0 = r,
+            None => self.0 = Increment::MAX_INCREMENT,
+        }
+    }
+}
+
+/// Prompts the user to pick a preset or to set the time, the time control mode,
+/// the per-move increment or delay of each player, any later stages of the time
+/// control and what happens once the main time runs out using the provided pins
+/// and display. A time control set by hand can be saved as a user preset, and
+/// whatever is picked is saved as the last used one. Blocks.
+///
+/// # Usage
+/// ```ignore
+/// time_set(
+///   pins.d1.into_input(), // Down button
+///   pins.d2.into_input(), // Up button
+///   pins.d3.into_input()  // Select button
+/// );
+/// ```
+pub fn time_set<
+    DP: Button,
+    UP: Button,
+    SP: Button,
+    S: SerialPort,
+    E: Eeprom,
+    T: TimeSource,
+    D: ClockDisplay,
+>(
+    down_pin: &mut DP,
+    up_pin: &mut UP,
+    start_pin: &mut SP,
+    serial_handler: &mut SerialHandler<S>,
+    storage: &mut Storage<E>,
+    time: &mut T,
+    display: &mut D,
+) -> Result<((TimeSetting, TimeSetting), TimeControl), RuntimeError> {
+    let mut state = TimeSetPart::Preset;
+    // Start from the last used time control if there is one
+    let mut preset_index = next_menu_entry(storage, CUSTOM_ENTRY, true);
+    let mut loaded_preset = usize::MAX;
+    // The overtime mode of the loaded preset, whose count and period are kept
+    // unless the mode is changed
+    let mut loaded_overtime_mode = OvertimeMode::None;
+    // 0 is not saving
+    let mut save_slot = 0;
+    let mut p1_setting = TimeSetting::new(0);
+    let mut p2_setting = TimeSetting::new(0);
+    let mut mode = TimeControlMode::Fischer;
+    let mut p1_increment = Increment::new(0);
+    let mut p2_increment = Increment::new(0);
+    let mut stages = [Stage::new(); MAX_STAGES];
+    let mut stage_index = 0;
+    let mut overtime = Overtime::new(OvertimeMode::None);
+
+    let mut down = debounce_4(false);
+    let mut down_hold_count: u16 = 0;
+    let mut up = debounce_4(false);
+    let mut up_hold_count: u16 = 0;
+    let mut start = debounce_4(false);
+
+    let mut blink_count = 0;
+    let mut last_p1_setting = TimeSetting::new(u16::MAX);
+    let mut last_p2_setting = TimeSetting::new(u16::MAX);
+    let mut last_mode = None;
+    let mut last_p1_increment = Increment::new(u8::MAX);
+    let mut last_p2_increment = Increment::new(u8::MAX);
+    let mut last_stages = stages;
+    let mut last_overtime = overtime;
+    let mut last_blink = Some(TimeSetPart::Preset);
+    let mut redraw = false;
+    loop {
+        // Nothing from the other side matters while setting up, but whoever
+        // connects gets the times being set straight away
+        serial_handler.read();
+        if serial_handler.keep_alive(time) {
+            last_p1_setting = TimeSetting::new(u16::MAX);
+            last_p2_setting = TimeSetting::new(u16::MAX);
+            last_mode = None;
+            last_p1_increment = Increment::new(u8::MAX);
+            last_p2_increment = Increment::new(u8::MAX);
+        }
+
+        // Change blinks
+        blink_count += 1;
+        let blink = blink_count >= BLINK_DURATION;
+        if blink_count >= BLINK_DURATION * 2 {
+            blink_count = 0;
+        }
+
+        // Update states
+        if up.update(
+            up_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Rising)
+        {
+            // Up press
+            match state {
+                TimeSetPart::Preset => preset_index = next_menu_entry(storage, preset_index, true),
+                TimeSetPart::P1SetMin => p1_setting += 60,
+                TimeSetPart::P1SetSec => p1_setting += 1,
+                TimeSetPart::P2SetMin => p2_setting += 60,
+                TimeSetPart::P2SetSec => p2_setting += 1,
+                TimeSetPart::SetMode => mode = mode.next(),
+                TimeSetPart::P1SetInc => p1_increment += 1,
+                TimeSetPart::P2SetInc => p2_increment += 1,
+                TimeSetPart::StageMoves => {
+                    stages[stage_index].after_moves =
+                        wrapping_add(stages[stage_index].after_moves, 1, 0, MAX_STAGE_MOVES)
+                }
+                TimeSetPart::StageMin => stages[stage_index].time += 60,
+                TimeSetPart::StageSec => stages[stage_index].time += 1,
+                TimeSetPart::StageInc => stages[stage_index].increment += 1,
+                TimeSetPart::SetOvertime => overtime.mode = overtime.mode.next(),
+                TimeSetPart::OvertimeCount => {
+                    overtime.count = wrapping_add(overtime.count, 1, 1, MAX_OVERTIME_COUNT)
+                }
+                TimeSetPart::OvertimeMin => overtime.period += 60,
+                TimeSetPart::OvertimeSec => match overtime.mode {
+                    OvertimeMode::Canadian => overtime.period += 1,
+                    _ => {
+                        overtime.period = TimeSetting::new(wrapping_add(
+                            overtime.period.into_secs() as u8,
+                            1,
+                            1,
+                            MAX_BYO_YOMI_PERIOD,
+                        ) as u16)
+                    }
+                },
+                TimeSetPart::SavePreset => {
+                    save_slot = wrapping_add(save_slot as u8, 1, 0, USER_SLOTS as u8) as usize
+                }
+            }
+            up_hold_count = 0;
+            blink_count = 0;
+        }
+        if up_hold_count == HOLD_THRESHOLD {
+            // Up hold
+            match state {
+                TimeSetPart::Preset => preset_index = next_menu_entry(storage, preset_index, true),
+                TimeSetPart::P1SetMin => p1_setting += 60,
+                TimeSetPart::P1SetSec => p1_setting += 5,
+                TimeSetPart::P2SetMin => p2_setting += 60,
+                TimeSetPart::P2SetSec => p2_setting += 5,
+                TimeSetPart::SetMode => mode = mode.next(),
+                TimeSetPart::P1SetInc => p1_increment += 1,
+                TimeSetPart::P2SetInc => p2_increment += 1,
+                TimeSetPart::StageMoves => {
+                    stages[stage_index].after_moves =
+                        wrapping_add(stages[stage_index].after_moves, 5, 0, MAX_STAGE_MOVES)
+                }
+                TimeSetPart::StageMin => stages[stage_index].time += 60,
+                TimeSetPart::StageSec => stages[stage_index].time += 5,
+                TimeSetPart::StageInc => stages[stage_index].increment += 1,
+                TimeSetPart::SetOvertime => overtime.mode = overtime.mode.next(),
+                TimeSetPart::OvertimeCount => {
+                    overtime.count = wrapping_add(overtime.count, 5, 1, MAX_OVERTIME_COUNT)
+                }
+                TimeSetPart::OvertimeMin => overtime.period += 60,
+                TimeSetPart::OvertimeSec => match overtime.mode {
+                    OvertimeMode::Canadian => overtime.period += 5,
+                    _ => {
+                        overtime.period = TimeSetting::new(wrapping_add(
+                            overtime.period.into_secs() as u8,
+                            5,
+                            1,
+                            MAX_BYO_YOMI_PERIOD,
+                        ) as u16)
+                    }
+                },
+                TimeSetPart::SavePreset => {
+                    save_slot = wrapping_add(save_slot as u8, 1, 0, USER_SLOTS as u8) as usize
+                }
+            }
+            blink_count = 0;
+            up_hold_count += 1;
+        }
+        if up.is_high() {
+            up_hold_count += 1;
+        }
+        if up_hold_count >= HOLD_THRESHOLD + REPEAT_THRESHOLD {
+            up_hold_count = HOLD_THRESHOLD
+        }
+
+        if down.update(
+            down_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Rising)
+        {
+            // Down press
+            match state {
+                TimeSetPart::Preset => preset_index = next_menu_entry(storage, preset_index, false),
+                TimeSetPart::P1SetMin => p1_setting -= 60,
+                TimeSetPart::P1SetSec => p1_setting -= 1,
+                TimeSetPart::P2SetMin => p2_setting -= 60,
+                TimeSetPart::P2SetSec => p2_setting -= 1,
+                TimeSetPart::SetMode => mode = mode.prev(),
+                TimeSetPart::P1SetInc => p1_increment -= 1,
+                TimeSetPart::P2SetInc => p2_increment -= 1,
+                TimeSetPart::StageMoves => {
+                    stages[stage_index].after_moves =
+                        wrapping_sub(stages[stage_index].after_moves, 1, 0, MAX_STAGE_MOVES)
+                }
+                TimeSetPart::StageMin => stages[stage_index].time -= 60,
+                TimeSetPart::StageSec => stages[stage_index].time -= 1,
+                TimeSetPart::StageInc => stages[stage_index].increment -= 1,
+                TimeSetPart::SetOvertime => overtime.mode = overtime.mode.prev(),
+                TimeSetPart::OvertimeCount => {
+                    overtime.count = wrapping_sub(overtime.count, 1, 1, MAX_OVERTIME_COUNT)
+                }
+                TimeSetPart::OvertimeMin => overtime.period -= 60,
+                TimeSetPart::OvertimeSec => match overtime.mode {
+                    OvertimeMode::Canadian => overtime.period -= 1,
+                    _ => {
+                        overtime.period = TimeSetting::new(wrapping_sub(
+                            overtime.period.into_secs() as u8,
+                            1,
+                            1,
+                            MAX_BYO_YOMI_PERIOD,
+                        ) as u16)
+                    }
+                },
+                TimeSetPart::SavePreset => {
+                    save_slot = wrapping_sub(save_slot as u8, 1, 0, USER_SLOTS as u8) as usize
+                }
+            }
+            down_hold_count = 0;
+            blink_count = 0;
+        }
+        if down_hold_count == HOLD_THRESHOLD {
+            // Down hold
+            match state {
+                TimeSetPart::Preset => preset_index = next_menu_entry(storage, preset_index, false),
+                TimeSetPart::P1SetMin => p1_setting -= 60,
+                TimeSetPart::P1SetSec => p1_setting -= 5,
+                TimeSetPart::P2SetMin => p2_setting -= 60,
+                TimeSetPart::P2SetSec => p2_setting -= 5,
+                TimeSetPart::SetMode => mode = mode.prev(),
+                TimeSetPart::P1SetInc => p1_increment -= 1,
+                TimeSetPart::P2SetInc => p2_increment -= 1,
+                TimeSetPart::StageMoves => {
+                    stages[stage_index].after_moves =
+                        wrapping_sub(stages[stage_index].after_moves, 5, 0, MAX_STAGE_MOVES)
+                }
+                TimeSetPart::StageMin => stages[stage_index].time -= 60,
+                TimeSetPart::StageSec => stages[stage_index].time -= 5,
+                TimeSetPart::StageInc => stages[stage_index].increment -= 1,
+                TimeSetPart::SetOvertime => overtime.mode = overtime.mode.prev(),
+                TimeSetPart::OvertimeCount => {
+                    overtime.count = wrapping_sub(overtime.count, 5, 1, MAX_OVERTIME_COUNT)
+                }
+                TimeSetPart::OvertimeMin => overtime.period -= 60,
+                TimeSetPart::OvertimeSec => match overtime.mode {
+                    OvertimeMode::Canadian => overtime.period -= 5,
+                    _ => {
+                        overtime.period = TimeSetting::new(wrapping_sub(
+                            overtime.period.into_secs() as u8,
+                            5,
+                            1,
+                            MAX_BYO_YOMI_PERIOD,
+                        ) as u16)
+                    }
+                },
+                TimeSetPart::SavePreset => {
+                    save_slot = wrapping_sub(save_slot as u8, 1, 0, USER_SLOTS as u8) as usize
+                }
+            }
+            blink_count = 0;
+            down_hold_count += 1;
+        }
+        if down.is_high() {
+            down_hold_count += 1;
+        }
+        if down_hold_count >= HOLD_THRESHOLD + REPEAT_THRESHOLD {
+            down_hold_count = HOLD_THRESHOLD
+        }
+
+        if start.update(
+            start_pin
+                .is_pressed()
+                .map_err(|_| RuntimeError::PinReadError)?,
+        ) == Some(Edge::Falling)
+        {
+            // Start button released; go to next portion
+            state = match state {
+                TimeSetPart::Preset => {
+                    if preset_index != CUSTOM_ENTRY {
+                        // Everything's already been loaded from the preset
+                        break;
+                    }
+                    // Custom; switch over to setting the times by hand
+                    redraw = true;
+                    TimeSetPart::P1SetMin
+                }
+                TimeSetPart::P1SetMin => TimeSetPart::P1SetSec,
+                TimeSetPart::P1SetSec => TimeSetPart::P2SetMin,
+                TimeSetPart::P2SetMin => TimeSetPart::P2SetSec,
+                TimeSetPart::P2SetSec => {
+                    // Times are done; switch over to the mode screen
+                    redraw = true;
+                    TimeSetPart::SetMode
+                }
+                TimeSetPart::SetMode => {
+                    redraw = true;
+                    if mode == TimeControlMode::Hourglass {
+                        // An hourglass has no increments, stages or overtime
+                        TimeSetPart::SavePreset
+                    } else {
+                        // Mode is done; switch over to the increment screen
+                        TimeSetPart::P1SetInc
+                    }
+                }
+                TimeSetPart::P1SetInc => TimeSetPart::P2SetInc,
+                TimeSetPart::P2SetInc => {
+                    // Increments are done; move on to the later stages
+                    stages[stage_index].increment = p1_increment;
+                    redraw = true;
+                    TimeSetPart::StageMoves
+                }
+                TimeSetPart::StageMoves => {
+                    if stages[stage_index].after_moves == 0 {
+                        // No more stages; the current one lasts until overtime
+                        redraw = true;
+                        TimeSetPart::SetOvertime
+                    } else {
+                        TimeSetPart::StageMin
+                    }
+                }
+                TimeSetPart::StageMin => TimeSetPart::StageSec,
+                TimeSetPart::StageSec => TimeSetPart::StageInc,
+                TimeSetPart::StageInc => {
+                    redraw = true;
+                    if stage_index + 1 >= MAX_STAGES {
+                        TimeSetPart::SetOvertime
+                    } else {
+                        stage_index += 1;
+                        stages[stage_index].increment = stages[stage_index - 1].increment;
+                        TimeSetPart::StageMoves
+                    }
+                }
+                TimeSetPart::SetOvertime => {
+                    redraw = true;
+                    if overtime.mode == OvertimeMode::None {
+                        TimeSetPart::SavePreset
+                    } else {
+                        // Start from the usual settings for a newly chosen
+                        // overtime
+                        if overtime.mode != loaded_overtime_mode {
+                            overtime = Overtime::new(overtime.mode);
+                        }
+                        TimeSetPart::OvertimeCount
+                    }
+                }
+                TimeSetPart::OvertimeCount => {
+                    if overtime.mode == OvertimeMode::Canadian {
+                        TimeSetPart::OvertimeMin
+                    } else {
+                        TimeSetPart::OvertimeSec
+                    }
+                }
+                TimeSetPart::OvertimeMin => TimeSetPart::OvertimeSec,
+                TimeSetPart::OvertimeSec => {
+                    redraw = true;
+                    TimeSetPart::SavePreset
+                }
+                TimeSetPart::SavePreset => break,
+            }
+        }
+
+        // Load the highlighted preset so it's what gets sent over serial
+        if state == TimeSetPart::Preset && preset_index != loaded_preset {
+            // Custom starts from the last used time control
+            let (times, control) = menu_entry(storage, preset_index)
+                .or_else(|| storage.load(LAST_USED_SLOT))
+                .unwrap_or((
+                    (TimeSetting::new(0), TimeSetting::new(0)),
+                    TimeControl::new(),
+                ));
+            (p1_setting, p2_setting) = times;
+            mode = control.mode;
+            (p1_increment, p2_increment) = control.increments;
+            stages = control.stages;
+            overtime = control.overtime;
+            loaded_overtime_mode = overtime.mode;
+            loaded_preset = preset_index;
+        }
+
+        // Render results
+        let new_blink = if blink { Some(state) } else { None };
+        if p1_setting != last_p1_setting || p2_setting != last_p2_setting {
+            serial_handler.send_setting((p1_setting.into_millis(), p2_setting.into_millis()));
+        }
+        if last_mode != Some(mode) {
+            serial_handler.write(SerialMsg::Mode { mode: mode as u32 });
+        }
+        if p1_increment != last_p1_increment || p2_increment != last_p2_increment {
+            serial_handler.write(SerialMsg::Increment {
+                p1_increment: p1_increment.into_millis(),
+                p2_increment: p2_increment.into_millis(),
+            });
+        }
+        if p1_setting != last_p1_setting
+            || p2_setting != last_p2_setting
+            || last_mode != Some(mode)
+            || p1_increment != last_p1_increment
+            || p2_increment != last_p2_increment
+            || stages != last_stages
+            || overtime != last_overtime
+            || new_blink != last_blink
+            || redraw
+        {
+            let setting = match state {
+                TimeSetPart::Preset => Setting::Preset(preset_index),
+                TimeSetPart::SavePreset => Setting::SavePreset(save_slot),
+                TimeSetPart::SetMode => Setting::Mode(&mode),
+                TimeSetPart::P1SetInc | TimeSetPart::P2SetInc => {
+                    Setting::Increments(&p1_increment, &p2_increment, &mode)
+                }
+                TimeSetPart::StageMoves
+                | TimeSetPart::StageMin
+                | TimeSetPart::StageSec
+                | TimeSetPart::StageInc => Setting::Stage {
+                    index: stage_index,
+                    stage: &stages[stage_index],
+                    mode: &mode,
+                },
+                TimeSetPart::SetOvertime
+                | TimeSetPart::OvertimeCount
+                | TimeSetPart::OvertimeMin
+                | TimeSetPart::OvertimeSec => Setting::Overtime {
+                    part: state,
+                    overtime: &overtime,
+                },
+                _ => Setting::Times(&p1_setting, &p2_setting),
+            };
+            display
+                .draw_setting(&setting, new_blink)
+                .map_err(|_| RuntimeError::DisplayError)?;
+            last_p1_setting = p1_setting;
+            last_p2_setting = p2_setting;
+            last_mode = Some(mode);
+            last_p1_increment = p1_increment;
+            last_p2_increment = p2_increment;
+            last_stages = stages;
+            last_overtime = overtime;
+            last_blink = new_blink;
+            redraw = false;
+        } else {
+            time.delay_ms(LOOP_DELAY);
+        }
+    }
+    let times = (p1_setting, p2_setting);
+    let control = if mode == TimeControlMode::Hourglass {
+        // Whatever was loaded from the preset, an hourglass has no increments,
+        // stages or overtime
+        TimeControl {
+            mode,
+            ..TimeControl::new()
+        }
+    } else {
+        TimeControl {
+            mode,
+            increments: (p1_increment, p2_increment),
+            stages,
+            stage_count: stages
+                .iter()
+                .take_while(|stage| stage.after_moves != 0)
+                .count() as u8,
+            overtime,
+        }
+    };
+    if save_slot != 0 {
+        // User presets come straight after the last used slot
+        storage.save(LAST_USED_SLOT + save_slot, &times, &control);
+    }
+    storage.save(LAST_USED_SLOT, &times, &control);
+    Ok((times, control))
+}
+
+/// The time control at `index` in the preset menu, or `None` for "Custom" or
+/// an empty slot.
+fn menu_entry<E: Eeprom>(
+    storage: &Storage<E>,
+    index: usize,
+) -> Option<((TimeSetting, TimeSetting), TimeControl)> {
+    if index < SLOT_COUNT {
+        storage.load(index)
+    } else {
+        preset(index - SLOT_COUNT).map(|preset| ((preset.time, preset.time), preset.control))
+    }
+}
+
+/// The next entry of the preset menu after (or before) `index`, skipping empty
+/// slots.
+fn next_menu_entry<E: Eeprom>(storage: &Storage<E>, index: usize, forward: bool) -> usize {
+    let mut index = index;
+    loop {
+        index = if forward {
+            wrapping_add(index as u8, 1, 0, CUSTOM_ENTRY as u8) as usize
+        } else {
+            wrapping_sub(index as u8, 1, 0, CUSTOM_ENTRY as u8) as usize
+        };
+        // Custom is always there, so this ends
+        if index >= SLOT_COUNT || storage.load(index).is_some() {
+            break index;
+        }
+    }
+}
+
+/// Adds to a setting, going back to `min` if it goes past `max`.
+fn wrapping_add(value: u8, rhs: u8, min: u8, max: u8) -> u8 {
+    match value.checked_add(rhs) {
+        Some(r) if r <= max => r,
+        Some(_) | None => min,
+    }
+}
+
+/// Subtracts from a setting, wrapping to `max` if it goes below `min`.
+fn wrapping_sub(value: u8, rhs: u8, min: u8, max: u8) -> u8 {
+    match value.checked_sub(rhs) {
+        Some(r) if r >= min => r,
+        Some(_) | None => max,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_for_display() {
+        assert_eq!(TimeSetting::from_millis(0).into_secs(), 0);
+        assert_eq!(TimeSetting::from_millis(1).into_secs(), 1);
+        assert_eq!(
+            TimeSetting::from_millis(59_001).into_hrs_mins_secs(),
+            (0, 1, 0)
+        );
+        assert_eq!(
+            TimeSetting::from_millis(3_600_000).into_hrs_mins_secs(),
+            (1, 0, 0)
+        );
+        assert_eq!(TimeSetting::from_millis(9_301).into_tenths(), 94);
+        assert_eq!(TimeSetting::from_millis(9_400).into_tenths(), 94);
+    }
+
+    #[test]
+    fn settings_wrap() {
+        let mut time = TimeSetting::new(TimeSetting::MAX_TIME - 10);
+        time += 60;
+        assert_eq!(time, TimeSetting::new(0));
+        time -= 1;
+        assert_eq!(time, TimeSetting::new(TimeSetting::MAX_TIME));
+
+        let mut increment = Increment::new(Increment::MAX_INCREMENT);
+        increment += 1;
+        assert_eq!(increment, Increment::new(0));
+        increment -= 1;
+        assert_eq!(increment, Increment::new(Increment::MAX_INCREMENT));
+
+        assert_eq!(wrapping_add(4, 1, 1, 4), 1);
+        assert_eq!(wrapping_sub(1, 1, 1, 4), 4);
+    }
+}
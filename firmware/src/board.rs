@@ -0,0 +1,87 @@
+//! Glue between the Arduino's peripherals and the traits the clock core needs.
+
+use arduino_hal::{hal::Atmega, usart::UsartOps, Usart};
+use embedded_hal::{
+    digital::v2::{InputPin, OutputPin},
+    serial::Write,
+};
+use open_chess_clock_core::hal;
+use void::ResultVoidExt;
+
+use crate::{millis::millis, serial_rx};
+
+/// A button wired between a pull-up input and ground, so it reads low when
+/// pressed.
+pub struct PullUpButton<P: InputPin>(pub P);
+
+impl<P: InputPin> hal::Button for PullUpButton<P> {
+    type Error = P::Error;
+
+    fn is_pressed(&mut self) -> Result<bool, P::Error> {
+        self.0.is_low()
+    }
+}
+
+/// A buzzer driven straight from an output pin.
+pub struct PinBuzzer<P: OutputPin>(pub P);
+
+impl<P: OutputPin> hal::Buzzer for PinBuzzer<P> {
+    type Error = P::Error;
+
+    fn set_on(&mut self) -> Result<(), P::Error> {
+        self.0.set_high()
+    }
+
+    fn set_off(&mut self) -> Result<(), P::Error> {
+        self.0.set_low()
+    }
+}
+
+/// Sets `pin` high or low.
+pub fn set<P: OutputPin>(pin: &mut P, high: bool) -> Result<(), P::Error> {
+    if high {
+        pin.set_high()
+    } else {
+        pin.set_low()
+    }
+}
+
+/// The USB serial port, with what's received coming from the interrupt set up
+/// in [`crate::serial_rx`].
+pub struct UsartPort<USART: UsartOps<Atmega, RX, TX>, RX, TX>(pub Usart<USART, RX, TX>);
+
+impl<USART: UsartOps<Atmega, RX, TX>, RX, TX> hal::SerialPort for UsartPort<USART, RX, TX> {
+    fn read_byte(&mut self) -> Option<u8> {
+        serial_rx::read_byte()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        nb::block!(self.0.write(byte)).void_unwrap();
+    }
+}
+
+/// The ATmega328P's built-in EEPROM.
+pub struct Eeprom(pub arduino_hal::Eeprom);
+
+impl hal::Eeprom for Eeprom {
+    fn read_byte(&self, offset: u16) -> u8 {
+        self.0.read_byte(offset)
+    }
+
+    fn write_byte(&mut self, offset: u16, byte: u8) {
+        self.0.write_byte(offset, byte)
+    }
+}
+
+/// Time from the TC0 interrupt set up in [`crate::millis`].
+pub struct Clock;
+
+impl hal::TimeSource for Clock {
+    fn millis(&self) -> u32 {
+        millis()
+    }
+
+    fn delay_ms(&mut self, ms: u16) {
+        arduino_hal::delay_ms(ms)
+    }
+}
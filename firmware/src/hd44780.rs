@@ -0,0 +1,80 @@
+//! The HD44780 over `hd44780-driver`'s buses. The driver's own `HD44780` can't
+//! be used as is, since it keeps the command which addresses the custom
+//! characters to itself and so can't draw the big digits.
+
+use arduino_hal::{delay_ms, Delay};
+use hd44780_driver::{bus::DataBus, error::Error};
+use open_chess_clock_core::hal::{self, uWrite};
+
+/// An HD44780 in 4-bit mode with two (or four) lines, the display on and the
+/// cursor hidden.
+pub struct Hd44780<B: DataBus> {
+    bus: B,
+    delay: Delay,
+}
+
+impl<B: DataBus> Hd44780<B> {
+    /// Sets the display up from however it was left, which takes about 60ms.
+    pub fn new(bus: B) -> Result<Self, Error> {
+        let mut lcd = Hd44780 {
+            bus,
+            delay: Delay::new(),
+        };
+        // Wait for the power to settle, then switch to 4-bit mode in a way
+        // that works whether the display was in 4-bit or 8-bit mode before
+        delay_ms(50);
+        lcd.command(0x33)?;
+        delay_ms(5);
+        lcd.command(0x32)?;
+        // Two lines of 5x8 characters
+        lcd.command(0x28)?;
+        // Display on, with no cursor
+        lcd.command(0x0c)?;
+        // Move right after each character
+        lcd.command(0x06)?;
+        hal::Lcd::clear(&mut lcd)?;
+        Ok(lcd)
+    }
+
+    fn command(&mut self, command: u8) -> Result<(), Error> {
+        self.bus.write(command, false, &mut self.delay)
+    }
+
+    fn data(&mut self, byte: u8) -> Result<(), Error> {
+        self.bus.write(byte, true, &mut self.delay)
+    }
+}
+
+impl<B: DataBus> uWrite for Hd44780<B> {
+    type Error = Error;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Error> {
+        for byte in s.bytes() {
+            self.data(byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<B: DataBus> hal::Lcd for Hd44780<B> {
+    fn clear(&mut self) -> Result<(), Error> {
+        self.command(0x01)?;
+        // Much slower than any other command
+        delay_ms(2);
+        Ok(())
+    }
+
+    fn set_cursor_pos(&mut self, position: u8) -> Result<(), Error> {
+        self.command(0x80 | position)
+    }
+
+    /// Leaves the display writing to the custom characters, so the cursor has
+    /// to be set before writing anything else.
+    fn define_char(&mut self, index: u8, rows: &[u8; 8]) -> Result<(), Error> {
+        self.command(0x40 | ((index & 0x07) << 3))?;
+        for row in rows {
+            self.data(row & 0x1f)?;
+        }
+        Ok(())
+    }
+}
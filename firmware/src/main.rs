@@ -0,0 +1,196 @@
+#![no_std]
+#![no_main]
+#![feature(abi_avr_interrupt)]
+
+use arduino_hal::{default_serial, delay_ms};
+use board::{Clock, Eeprom, PinBuzzer, PullUpButton, UsartPort};
+#[cfg(any(feature = "parallel-lcd", feature = "i2c-lcd"))]
+use hd44780::Hd44780;
+#[cfg(feature = "parallel-lcd")]
+use hd44780_driver::bus::FourBitBus;
+#[cfg(feature = "i2c-lcd")]
+use hd44780_driver::bus::I2CBus;
+#[cfg(feature = "max7219")]
+use max7219::Max7219Pair;
+#[cfg(not(any(
+    feature = "parallel-lcd",
+    feature = "i2c-lcd",
+    feature = "ssd1306-oled"
+)))]
+use open_chess_clock_core::display::NoStatus;
+#[cfg(feature = "ssd1306-oled")]
+use open_chess_clock_core::display::Oled;
+#[cfg(any(feature = "tm1637", feature = "max7219"))]
+use open_chess_clock_core::display::SegmentClocks;
+#[cfg(any(feature = "parallel-lcd", feature = "i2c-lcd"))]
+use open_chess_clock_core::display::{CharLcd, Geometry};
+use open_chess_clock_core::{display::ClockDisplay, serial::SerialHandler, storage::Storage};
+use panic_halt as _;
+#[cfg(feature = "ssd1306-oled")]
+use ssd1306::Ssd1306;
+#[cfg(feature = "tm1637")]
+use tm1637::{Tm1637, Tm1637Pair};
+
+mod board;
+#[cfg(any(feature = "parallel-lcd", feature = "i2c-lcd"))]
+mod hd44780;
+#[cfg(feature = "max7219")]
+mod max7219;
+mod millis;
+mod serial_rx;
+#[cfg(feature = "ssd1306-oled")]
+mod ssd1306;
+#[cfg(feature = "tm1637")]
+mod tm1637;
+
+#[cfg(any(
+    all(feature = "parallel-lcd", feature = "i2c-lcd"),
+    all(feature = "parallel-lcd", feature = "ssd1306-oled"),
+    all(feature = "i2c-lcd", feature = "ssd1306-oled"),
+))]
+compile_error!("pick one of the `parallel-lcd`, `i2c-lcd` and `ssd1306-oled` features");
+#[cfg(all(feature = "tm1637", feature = "max7219"))]
+compile_error!("pick one of the `tm1637` and `max7219` features");
+#[cfg(not(any(
+    feature = "parallel-lcd",
+    feature = "i2c-lcd",
+    feature = "ssd1306-oled",
+    feature = "tm1637",
+    feature = "max7219"
+)))]
+compile_error!(
+    "pick the display and how it's wired with the `parallel-lcd`, `i2c-lcd` or `ssd1306-oled` feature, and the segment displays with `tm1637` or `max7219`"
+);
+
+/// The size of the LCD. A 20x4 one shows the moves and increments under the
+/// clocks too.
+#[cfg(any(feature = "parallel-lcd", feature = "i2c-lcd"))]
+const LCD_GEOMETRY: Geometry = Geometry::LCD_16X2;
+/// The I2C address of the LCD's backpack. Most PCF8574 backpacks are at 0x27,
+/// and PCF8574A ones at 0x3F. Some have jumpers to change it.
+#[cfg(feature = "i2c-lcd")]
+const LCD_I2C_ADDRESS: u8 = 0x27;
+/// How fast to talk to the LCD's backpack, in Hz. The PCF8574 manages 100kHz.
+#[cfg(feature = "i2c-lcd")]
+const LCD_I2C_SPEED: u32 = 100_000;
+/// The I2C address of the OLED. Most are at 0x3C, and some can be moved to
+/// 0x3D with a jumper or by moving a resistor.
+#[cfg(feature = "ssd1306-oled")]
+const OLED_I2C_ADDRESS: u8 = 0x3c;
+/// How fast to talk to the OLED, in Hz. The SSD1306 manages 400kHz.
+#[cfg(feature = "ssd1306-oled")]
+const OLED_I2C_SPEED: u32 = 400_000;
+
+#[arduino_hal::entry]
+fn main() -> ! {
+    let dp = arduino_hal::Peripherals::take().unwrap();
+    let pins = arduino_hal::pins!(dp);
+
+    // Initialize peripherals
+    millis::init(dp.TC0);
+
+    let mut builtin_led = pins.d13.into_output_high();
+    let down_btn = PullUpButton(pins.d2.into_pull_up_input()); // Also P1 button
+    let up_btn = PullUpButton(pins.d4.into_pull_up_input()); // Also P2 button
+    let start_btn = PullUpButton(pins.d3.into_pull_up_input());
+
+    let buzzer = PinBuzzer(pins.d6.into_output());
+
+    let mut serial = default_serial!(dp, pins, 57600);
+    serial_rx::init(&mut serial);
+    let serial_handler = SerialHandler::new(UsartPort(serial));
+
+    let storage = Storage::new(Eeprom(arduino_hal::Eeprom::new(dp.EEPROM)));
+
+    #[cfg(feature = "parallel-lcd")]
+    let hd44780 = Hd44780::new(FourBitBus::from_pins(
+        pins.d7.into_output(),  // RS
+        pins.d8.into_output(),  // EN
+        pins.d9.into_output(),  // d4
+        pins.d10.into_output(), // d5
+        pins.d11.into_output(), // d6
+        pins.d12.into_output(), // d7
+    ))
+    .unwrap();
+    #[cfg(feature = "i2c-lcd")]
+    let hd44780 = Hd44780::new(I2CBus::new(
+        arduino_hal::I2c::new(
+            dp.TWI,
+            pins.a4.into_pull_up_input(),
+            pins.a5.into_pull_up_input(),
+            LCD_I2C_SPEED,
+        ),
+        LCD_I2C_ADDRESS,
+    ))
+    .unwrap();
+    #[cfg(any(feature = "parallel-lcd", feature = "i2c-lcd"))]
+    let status = CharLcd::with_geometry(hd44780, LCD_GEOMETRY);
+    #[cfg(feature = "ssd1306-oled")]
+    let status = Oled::new(
+        Ssd1306::new(
+            arduino_hal::I2c::new(
+                dp.TWI,
+                pins.a4.into_pull_up_input(),
+                pins.a5.into_pull_up_input(),
+                OLED_I2C_SPEED,
+            ),
+            OLED_I2C_ADDRESS,
+        )
+        .unwrap(),
+    );
+    #[cfg(not(any(
+        feature = "parallel-lcd",
+        feature = "i2c-lcd",
+        feature = "ssd1306-oled"
+    )))]
+    let status = NoStatus;
+
+    // The segment displays facing the players show their times, and the
+    // display above everything else
+    #[cfg(feature = "tm1637")]
+    let mut display = SegmentClocks::new(
+        Tm1637Pair {
+            p1: Tm1637::new(pins.a0.into_output(), pins.a1.into_opendrain_high()),
+            p2: Tm1637::new(pins.a2.into_output(), pins.a3.into_opendrain_high()),
+        },
+        status,
+    );
+    #[cfg(feature = "max7219")]
+    let mut display = SegmentClocks::new(
+        Max7219Pair::new(
+            pins.a0.into_output(),
+            pins.a1.into_output(),
+            pins.a2.into_output(),
+        )
+        .unwrap(),
+        status,
+    );
+    #[cfg(not(any(feature = "tm1637", feature = "max7219")))]
+    let mut display = status;
+
+    // Enable interrupts! Whoo! Things can break!
+    unsafe { avr_device::interrupt::enable() };
+
+    // Turn off the init light to show the successful end of initialization
+    builtin_led.set_low();
+
+    // The main runtime is in a wrapper to handle errors properly
+    if let Err(err) = open_chess_clock_core::run(
+        down_btn,
+        up_btn,
+        start_btn,
+        buzzer,
+        serial_handler,
+        storage,
+        &mut Clock,
+        &mut display,
+        env!("CARGO_PKG_VERSION"),
+    ) {
+        let _ = display.draw_error(err.name());
+    }
+    // Something went very wrong; blink the LED fast.
+    loop {
+        builtin_led.toggle();
+        delay_ms(200);
+    }
+}
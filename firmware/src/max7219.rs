@@ -0,0 +1,107 @@
+//! Just enough of a driver for two MAX7219 seven-segment modules daisy-chained
+//! on three pins, one facing each player.
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::v2::OutputPin;
+use open_chess_clock_core::{countdown::Turn, hal};
+
+use crate::board::set;
+
+const NO_OP: u8 = 0x00;
+/// The register of the rightmost digit, with the others after it.
+const DIGIT_0: u8 = 0x01;
+const DECODE_MODE: u8 = 0x09;
+const INTENSITY: u8 = 0x0a;
+const SCAN_LIMIT: u8 = 0x0b;
+const SHUTDOWN: u8 = 0x0c;
+const DISPLAY_TEST: u8 = 0x0f;
+
+/// Two MAX7219s sharing DIN, CLK and CS, with P1's wired to the Arduino and
+/// P2's to P1's DOUT. Only the rightmost four digits of each are used.
+pub struct Max7219Pair<DIN, CLK, CS> {
+    din: DIN,
+    clk: CLK,
+    cs: CS,
+}
+
+impl<DIN, CLK, CS> Max7219Pair<DIN, CLK, CS>
+where
+    DIN: OutputPin<Error = Infallible>,
+    CLK: OutputPin<Error = Infallible>,
+    CS: OutputPin<Error = Infallible>,
+{
+    /// Sets both modules up and leaves them blank.
+    pub fn new(din: DIN, clk: CLK, mut cs: CS) -> Result<Self, Infallible> {
+        cs.set_high()?;
+        let mut pair = Max7219Pair { din, clk, cs };
+        for (register, value) in [
+            (DISPLAY_TEST, 0),
+            // Segments rather than BCD digits
+            (DECODE_MODE, 0),
+            (SCAN_LIMIT, 3),
+            (INTENSITY, 8),
+            (SHUTDOWN, 1),
+        ] {
+            pair.write([(register, value), (register, value)])?;
+        }
+        for player in [Turn::P1, Turn::P2] {
+            hal::SegmentDisplays::show(&mut pair, &player, &[0; 4], false)?;
+        }
+        Ok(pair)
+    }
+
+    /// Writes P2's register and then P1's, which it pushes on down the chain.
+    fn write(&mut self, words: [(u8, u8); 2]) -> Result<(), Infallible> {
+        self.cs.set_low()?;
+        for (register, value) in words {
+            for byte in [register, value] {
+                // Most significant bit first, each read as CLK rises
+                for bit in (0..8).rev() {
+                    set(&mut self.din, (byte >> bit) & 1 != 0)?;
+                    self.clk.set_high()?;
+                    self.clk.set_low()?;
+                }
+            }
+        }
+        // Both take their words as CS rises
+        self.cs.set_high()
+    }
+}
+
+impl<DIN, CLK, CS> hal::SegmentDisplays for Max7219Pair<DIN, CLK, CS>
+where
+    DIN: OutputPin<Error = Infallible>,
+    CLK: OutputPin<Error = Infallible>,
+    CS: OutputPin<Error = Infallible>,
+{
+    type Error = Infallible;
+
+    /// Shows the colon with the second digit's decimal point, since the
+    /// modules don't have one.
+    fn show(&mut self, player: &Turn, digits: &[u8; 4], colon: bool) -> Result<(), Infallible> {
+        for (i, &segments) in digits.iter().enumerate() {
+            let segments = if i == 1 && colon {
+                segments | 0x80
+            } else {
+                segments
+            };
+            let word = (DIGIT_0 + 3 - i as u8, reorder(segments));
+            self.write(match player {
+                Turn::P1 => [(NO_OP, 0), word],
+                Turn::P2 => [word, (NO_OP, 0)],
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Moves segments A to G from bits 0 to 6 to bits 6 to 0, where the MAX7219
+/// wants them, leaving the decimal point in bit 7.
+fn reorder(segments: u8) -> u8 {
+    (0..7)
+        .filter(|segment| (segments >> segment) & 1 != 0)
+        .fold(segments & 0x80, |reordered, segment| {
+            reordered | (1 << (6 - segment))
+        })
+}
@@ -0,0 +1,69 @@
+//! Bytes from the USB serial port, taken off the USART by its RX interrupt as
+//! they arrive and kept until the clock gets round to them. The USART only
+//! holds two bytes itself, which at 57600 baud is less time than it takes to
+//! draw a screen.
+
+use core::cell::RefCell;
+
+use arduino_hal::{hal::usart::Event, pac::USART0, Usart};
+use avr_device::interrupt::Mutex;
+
+/// How many bytes can wait to be read, which is a few frames.
+const CAPACITY: usize = 64;
+
+/// The bytes which have arrived but haven't been read, oldest first.
+struct RingBuffer {
+    bytes: [u8; CAPACITY],
+    start: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    const fn new() -> Self {
+        RingBuffer {
+            bytes: [0; CAPACITY],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Adds `byte` to the end, or drops it if there's no room. The frame it
+    /// was in is spoilt either way, but the ones after it get through.
+    fn push(&mut self, byte: u8) {
+        if self.len < CAPACITY {
+            self.bytes[(self.start + self.len) % CAPACITY] = byte;
+            self.len += 1;
+        }
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.bytes[self.start];
+        self.start = (self.start + 1) % CAPACITY;
+        self.len -= 1;
+        Some(byte)
+    }
+}
+
+static RECEIVED: Mutex<RefCell<RingBuffer>> = Mutex::new(RefCell::new(RingBuffer::new()));
+
+/// Has the RX interrupt take each byte off `usart` as it arrives. Read them
+/// with [`read_byte`] rather than from `usart`.
+pub fn init<RX, TX>(usart: &mut Usart<USART0, RX, TX>) {
+    usart.listen(Event::RxComplete);
+}
+
+#[avr_device::interrupt(atmega328p)]
+fn USART_RX() {
+    // SAFETY: nothing else reads UDR0 once the interrupt is on, and reading it
+    // is what clears the interrupt
+    let byte = unsafe { &*USART0::ptr() }.udr0.read().bits();
+    avr_device::interrupt::free(|cs| RECEIVED.borrow(cs).borrow_mut().push(byte));
+}
+
+/// Takes the oldest byte which hasn't been read yet, if there is one.
+pub fn read_byte() -> Option<u8> {
+    avr_device::interrupt::free(|cs| RECEIVED.borrow(cs).borrow_mut().pop())
+}
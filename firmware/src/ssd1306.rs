@@ -0,0 +1,72 @@
+//! Just enough of a driver for a 128x64 SSD1306 OLED on I2C to show the clock.
+
+use embedded_hal::blocking::i2c;
+use open_chess_clock_core::hal;
+
+/// Sent before commands.
+const COMMANDS: u8 = 0x00;
+/// Sent before pixels.
+const DATA: u8 = 0x40;
+/// How many bytes of pixels to send at a time, to keep the buffer small.
+const CHUNK: usize = 16;
+
+/// What a 128x64 module needs, with the charge pump on, counting pages from
+/// the top and columns from the left as it's usually mounted.
+const INIT: [u8; 25] = [
+    0xae, // Display off
+    0xd5, 0x80, // Clock divide ratio and oscillator frequency
+    0xa8, 0x3f, // 64 rows
+    0xd3, 0x00, // No vertical offset
+    0x40, // Start at row 0
+    0x8d, 0x14, // Charge pump on
+    0x20, 0x02, // Page addressing
+    0xa1, // Column 127 on the left, so column 0 is on the left as mounted
+    0xc8, // Rows scanned from the bottom, for the same reason
+    0xda, 0x12, // Alternate row wiring
+    0x81, 0xcf, // Contrast
+    0xd9, 0xf1, // Pre-charge period
+    0xdb, 0x40, // VCOMH level
+    0xa4, // Show what's in memory
+    0xa6, // Lit pixels are 1s
+    0xaf, // Display on
+];
+
+/// An SSD1306 at `address` on the I2C bus.
+pub struct Ssd1306<I> {
+    i2c: I,
+    address: u8,
+}
+
+impl<I: i2c::Write> Ssd1306<I> {
+    /// Sets the display up and clears it.
+    pub fn new(i2c: I, address: u8) -> Result<Self, I::Error> {
+        let mut oled = Ssd1306 { i2c, address };
+        oled.commands(&INIT)?;
+        for page in 0..8 {
+            hal::PagedDisplay::write_page(&mut oled, page, 0, &[0; 128])?;
+        }
+        Ok(oled)
+    }
+
+    fn commands(&mut self, commands: &[u8]) -> Result<(), I::Error> {
+        for &command in commands {
+            self.i2c.write(self.address, &[COMMANDS, command])?;
+        }
+        Ok(())
+    }
+}
+
+impl<I: i2c::Write> hal::PagedDisplay for Ssd1306<I> {
+    type Error = I::Error;
+
+    fn write_page(&mut self, page: u8, column: u8, columns: &[u8]) -> Result<(), I::Error> {
+        self.commands(&[0xb0 | (page & 7), column & 0x0f, 0x10 | (column >> 4)])?;
+        // The column moves on by itself after each byte
+        let mut buffer = [DATA; CHUNK + 1];
+        for chunk in columns.chunks(CHUNK) {
+            buffer[1..=chunk.len()].copy_from_slice(chunk);
+            self.i2c.write(self.address, &buffer[..=chunk.len()])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//! Just enough of a driver for a pair of four-digit TM1637 modules, one facing
+//! each player, each on its own two pins.
+
+use core::convert::Infallible;
+
+use arduino_hal::delay_us;
+use embedded_hal::digital::v2::OutputPin;
+use open_chess_clock_core::{countdown::Turn, hal};
+
+use crate::board::set;
+
+/// Write the digits from the first one on.
+const AUTO_INCREMENT: u8 = 0x40;
+/// The address of the first digit.
+const FIRST_DIGIT: u8 = 0xc0;
+/// Display on, at a brightness from 0 to 7.
+const DISPLAY_ON: u8 = 0x88 | 4;
+/// How long each half of a clock cycle lasts, in microseconds.
+const HALF_CLOCK_US: u32 = 5;
+
+/// A TM1637 with CLK on an output pin and DIO on an open-drain one, which the
+/// module pulls up. The TM1637 pulls DIO low to acknowledge each byte, so DIO
+/// is only ever let go of rather than driven high.
+pub struct Tm1637<CLK, DIO> {
+    clk: CLK,
+    dio: DIO,
+}
+
+impl<CLK, DIO> Tm1637<CLK, DIO>
+where
+    CLK: OutputPin<Error = Infallible>,
+    DIO: OutputPin<Error = Infallible>,
+{
+    pub fn new(mut clk: CLK, mut dio: DIO) -> Self {
+        let _ = clk.set_high();
+        let _ = dio.set_high();
+        Tm1637 { clk, dio }
+    }
+
+    /// Shows `digits`, with the colon wired to the second digit's decimal
+    /// point as on most modules.
+    fn show(&mut self, digits: &[u8; 4], colon: bool) -> Result<(), Infallible> {
+        self.command(&[AUTO_INCREMENT])?;
+        let colon = if colon { 0x80 } else { 0 };
+        self.command(&[
+            FIRST_DIGIT,
+            digits[0],
+            digits[1] | colon,
+            digits[2],
+            digits[3],
+        ])?;
+        self.command(&[DISPLAY_ON])
+    }
+
+    /// Sends `bytes` between a start and a stop.
+    fn command(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+        // Start: DIO falls while CLK is high
+        self.dio.set_low()?;
+        delay_us(HALF_CLOCK_US);
+        for &byte in bytes {
+            self.write_byte(byte)?;
+        }
+        // Stop: DIO rises while CLK is high
+        self.clk.set_low()?;
+        self.dio.set_low()?;
+        delay_us(HALF_CLOCK_US);
+        self.clk.set_high()?;
+        delay_us(HALF_CLOCK_US);
+        self.dio.set_high()?;
+        delay_us(HALF_CLOCK_US);
+        Ok(())
+    }
+
+    fn write_byte(&mut self, byte: u8) -> Result<(), Infallible> {
+        // Least significant bit first, each read as CLK rises
+        for bit in 0..8 {
+            self.clk.set_low()?;
+            set(&mut self.dio, (byte >> bit) & 1 != 0)?;
+            delay_us(HALF_CLOCK_US);
+            self.clk.set_high()?;
+            delay_us(HALF_CLOCK_US);
+        }
+        // Let go of DIO as CLK falls, when the TM1637 starts acknowledging
+        // the byte, and clock the acknowledgement through without reading it
+        self.clk.set_low()?;
+        self.dio.set_high()?;
+        delay_us(HALF_CLOCK_US);
+        self.clk.set_high()?;
+        delay_us(HALF_CLOCK_US);
+        self.clk.set_low()
+    }
+}
+
+/// The TM1637 facing each player.
+pub struct Tm1637Pair<CLK1, DIO1, CLK2, DIO2> {
+    pub p1: Tm1637<CLK1, DIO1>,
+    pub p2: Tm1637<CLK2, DIO2>,
+}
+
+impl<CLK1, DIO1, CLK2, DIO2> hal::SegmentDisplays for Tm1637Pair<CLK1, DIO1, CLK2, DIO2>
+where
+    CLK1: OutputPin<Error = Infallible>,
+    DIO1: OutputPin<Error = Infallible>,
+    CLK2: OutputPin<Error = Infallible>,
+    DIO2: OutputPin<Error = Infallible>,
+{
+    type Error = Infallible;
+
+    fn show(&mut self, player: &Turn, digits: &[u8; 4], colon: bool) -> Result<(), Infallible> {
+        match player {
+            Turn::P1 => self.p1.show(digits, colon),
+            Turn::P2 => self.p2.show(digits, colon),
+        }
+    }
+}
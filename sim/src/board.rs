@@ -0,0 +1,173 @@
+//! The simulated hardware, all sharing one [`Panel`] so that keys pressed in
+//! the terminal reach the buttons and whatever's written to the LCD gets drawn.
+
+use std::{
+    cell::RefCell,
+    process,
+    rc::Rc,
+    sync::mpsc::Receiver,
+    thread,
+    time::{Duration, Instant},
+};
+
+use open_chess_clock_core::{countdown::Turn, display::Geometry};
+use open_chess_clock_sim::{
+    hardware::{Hardware, Input},
+    lcd::Ddram,
+    oled::Gddram,
+    segments::SegmentPair,
+};
+
+use crate::terminal::{self, Key, Terminal};
+
+/// How many times a tapped button reads as pressed before it's let go. The
+/// screens check their buttons about every 5ms, so this is a short press.
+const TAP_POLLS: u16 = 20;
+const MAX_SPEED: u32 = 64;
+
+pub struct Panel {
+    pub terminal: Terminal,
+    keys: Receiver<Key>,
+    lcd: Ddram,
+    geometry: Geometry,
+    /// The OLED, if the clock is running on one instead of the LCD
+    oled: Option<Gddram>,
+    /// The displays facing each player, if there are any
+    segments: Option<SegmentPair>,
+    /// The number of polls each of down, start and up still reads as pressed
+    taps: [u16; 3],
+    /// Whether each of down, start and up is held down
+    holds: [bool; 3],
+    buzzing: bool,
+    speed: u32,
+    /// The real time and simulated ms when the speed last changed
+    base: (Instant, u32),
+    dirty: bool,
+}
+
+pub type SharedPanel = Rc<RefCell<Panel>>;
+
+impl Panel {
+    pub fn new(
+        terminal: Terminal,
+        keys: Receiver<Key>,
+        geometry: Geometry,
+        oled: bool,
+        segments: bool,
+        speed: u32,
+    ) -> SharedPanel {
+        Rc::new(RefCell::new(Panel {
+            terminal,
+            keys,
+            lcd: Ddram::new(),
+            geometry,
+            oled: oled.then(Gddram::new),
+            segments: segments.then(SegmentPair::new),
+            taps: [0; 3],
+            holds: [false; 3],
+            buzzing: false,
+            speed: speed.clamp(1, MAX_SPEED),
+            base: (Instant::now(), 0),
+            dirty: true,
+        }))
+    }
+
+    fn set_speed(&mut self, speed: u32) {
+        self.base = (Instant::now(), self.millis());
+        self.speed = speed.clamp(1, MAX_SPEED);
+        self.dirty = true;
+    }
+
+    /// Handles the keys pressed since last time and redraws the screen if
+    /// anything changed.
+    fn update(&mut self) {
+        while let Ok(key) = self.keys.try_recv() {
+            match key {
+                Key::Tap(button) => self.taps[button] = TAP_POLLS,
+                Key::Hold(button) => self.holds[button] = !self.holds[button],
+                Key::Faster => self.set_speed(self.speed * 2),
+                Key::Slower => self.set_speed(self.speed / 2),
+                Key::Quit => {
+                    self.terminal.restore();
+                    process::exit(0);
+                }
+            }
+            self.dirty = true;
+        }
+        if self.dirty {
+            let rows = match &self.oled {
+                Some(oled) => oled.rows(),
+                None => self.lcd.rows(self.geometry),
+            };
+            let segments = self
+                .segments
+                .as_ref()
+                .map(|segments| [segments.line(&Turn::P1), segments.line(&Turn::P2)]);
+            terminal::draw(
+                &rows,
+                segments,
+                self.buzzing,
+                self.holds,
+                self.millis(),
+                self.speed,
+            );
+            self.dirty = false;
+        }
+    }
+}
+
+/// Simulated time runs `speed` times faster than real time.
+impl Hardware for Panel {
+    fn millis(&self) -> u32 {
+        let real = self.base.0.elapsed().as_millis() as u64;
+        self.base.1.wrapping_add((real * self.speed as u64) as u32)
+    }
+
+    fn delay_ms(&mut self, ms: u16) {
+        self.update();
+        thread::sleep(Duration::from_micros(ms as u64 * 1000 / self.speed as u64));
+    }
+
+    fn is_pressed(&mut self, input: Input) -> Option<bool> {
+        let button = input as usize;
+        if self.holds[button] {
+            return Some(true);
+        }
+        let taps = &mut self.taps[button];
+        if *taps > 0 {
+            *taps -= 1;
+            return Some(true);
+        }
+        Some(false)
+    }
+
+    fn set_buzzing(&mut self, buzzing: bool) {
+        if self.buzzing != buzzing {
+            self.buzzing = buzzing;
+            self.dirty = true;
+        }
+    }
+
+    fn lcd(&mut self) -> &mut Ddram {
+        self.dirty = true;
+        &mut self.lcd
+    }
+
+    fn oled(&mut self) -> Option<&mut Gddram> {
+        self.dirty = true;
+        self.oled.as_mut()
+    }
+
+    fn segments(&mut self) -> Option<&mut SegmentPair> {
+        self.dirty = true;
+        self.segments.as_mut()
+    }
+
+    // There's no website to talk to, so nothing arrives and anything sent is
+    // lost
+    fn read_byte(&mut self) -> Option<u8> {
+        None
+    }
+
+    fn write_byte(&mut self, _byte: u8) {}
+}
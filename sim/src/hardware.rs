@@ -0,0 +1,220 @@
+//! The clock's hardware for both the simulator and the test harness, so the
+//! two can't drift apart.
+//!
+//! Each piece is a thin wrapper around the state they all share, which only
+//! has to implement [`Hardware`].
+
+use std::{cell::RefCell, convert::Infallible, fs, path::PathBuf, rc::Rc};
+
+use open_chess_clock_core::{
+    countdown::Turn,
+    hal::{
+        uWrite, Button, Buzzer, Eeprom, Lcd, PagedDisplay, SegmentDisplays, SerialPort, TimeSource,
+    },
+};
+
+use crate::{
+    lcd::{Ddram, InvalidPosition},
+    oled::{Gddram, InvalidPage},
+    segments::SegmentPair,
+};
+
+/// The size of the ATmega328P's EEPROM.
+pub const EEPROM_SIZE: usize = 1024;
+
+/// One of the clock's buttons.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Input {
+    /// Down, which is also P1's button
+    Down,
+    Start,
+    /// Up, which is also P2's button
+    Up,
+}
+
+/// Everything the virtual hardware shares.
+pub trait Hardware {
+    /// Milliseconds since the clock was switched on.
+    fn millis(&self) -> u32;
+
+    /// Lets `ms` go by, dealing with anything which happens meanwhile.
+    fn delay_ms(&mut self, ms: u16);
+
+    /// Whether `input` is held down right now, or `None` once the clock is to
+    /// stop.
+    fn is_pressed(&mut self, input: Input) -> Option<bool>;
+
+    fn set_buzzing(&mut self, buzzing: bool);
+
+    fn lcd(&mut self) -> &mut Ddram;
+
+    /// The OLED, if the clock is running on one instead of the LCD
+    fn oled(&mut self) -> Option<&mut Gddram>;
+
+    /// The displays facing each player, if there are any
+    fn segments(&mut self) -> Option<&mut SegmentPair>;
+
+    /// Reads a byte from the other end of the serial cable if one has arrived.
+    fn read_byte(&mut self) -> Option<u8>;
+
+    fn write_byte(&mut self, byte: u8);
+}
+
+pub struct VirtualTime<H: Hardware>(pub Rc<RefCell<H>>);
+
+impl<H: Hardware> TimeSource for VirtualTime<H> {
+    fn millis(&self) -> u32 {
+        self.0.borrow().millis()
+    }
+
+    fn delay_ms(&mut self, ms: u16) {
+        self.0.borrow_mut().delay_ms(ms);
+    }
+}
+
+/// Errors once the clock is to stop, which is the only way to stop it.
+pub struct VirtualButton<H: Hardware>(pub Rc<RefCell<H>>, pub Input);
+
+impl<H: Hardware> Button for VirtualButton<H> {
+    type Error = ();
+
+    fn is_pressed(&mut self) -> Result<bool, ()> {
+        self.0.borrow_mut().is_pressed(self.1).ok_or(())
+    }
+}
+
+pub struct VirtualBuzzer<H: Hardware>(pub Rc<RefCell<H>>);
+
+impl<H: Hardware> Buzzer for VirtualBuzzer<H> {
+    type Error = Infallible;
+
+    fn set_on(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().set_buzzing(true);
+        Ok(())
+    }
+
+    fn set_off(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().set_buzzing(false);
+        Ok(())
+    }
+}
+
+pub struct VirtualLcd<H: Hardware>(pub Rc<RefCell<H>>);
+
+impl<H: Hardware> uWrite for VirtualLcd<H> {
+    type Error = InvalidPosition;
+
+    fn write_str(&mut self, s: &str) -> Result<(), InvalidPosition> {
+        self.0.borrow_mut().lcd().write_str(s)
+    }
+}
+
+impl<H: Hardware> Lcd for VirtualLcd<H> {
+    fn clear(&mut self) -> Result<(), InvalidPosition> {
+        self.0.borrow_mut().lcd().clear()
+    }
+
+    fn set_cursor_pos(&mut self, position: u8) -> Result<(), InvalidPosition> {
+        self.0.borrow_mut().lcd().set_cursor_pos(position)
+    }
+
+    fn define_char(&mut self, index: u8, rows: &[u8; 8]) -> Result<(), InvalidPosition> {
+        self.0.borrow_mut().lcd().define_char(index, rows)
+    }
+}
+
+pub struct VirtualOled<H: Hardware>(pub Rc<RefCell<H>>);
+
+impl<H: Hardware> PagedDisplay for VirtualOled<H> {
+    type Error = InvalidPage;
+
+    fn write_page(&mut self, page: u8, column: u8, columns: &[u8]) -> Result<(), InvalidPage> {
+        match self.0.borrow_mut().oled() {
+            Some(oled) => oled.write_page(page, column, columns),
+            None => Err(InvalidPage(page, column)),
+        }
+    }
+}
+
+pub struct VirtualSegments<H: Hardware>(pub Rc<RefCell<H>>);
+
+impl<H: Hardware> SegmentDisplays for VirtualSegments<H> {
+    type Error = Infallible;
+
+    fn show(&mut self, player: &Turn, digits: &[u8; 4], colon: bool) -> Result<(), Infallible> {
+        if let Some(segments) = self.0.borrow_mut().segments() {
+            segments.show(player, digits, colon)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct VirtualSerial<H: Hardware>(pub Rc<RefCell<H>>);
+
+impl<H: Hardware> SerialPort for VirtualSerial<H> {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.0.borrow_mut().read_byte()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.0.borrow_mut().write_byte(byte);
+    }
+}
+
+/// EEPROM kept in memory, and in a file if there is one so that presets are
+/// still there next time. Clones share the same memory, so what's left in it
+/// can be read once the clock has stopped.
+#[derive(Clone)]
+pub struct VirtualEeprom {
+    bytes: Rc<RefCell<Vec<u8>>>,
+    path: Option<PathBuf>,
+}
+
+impl VirtualEeprom {
+    /// Starts out holding `bytes`, with anything after them erased.
+    pub fn new(bytes: &[u8]) -> VirtualEeprom {
+        let mut bytes = bytes.to_vec();
+        // Erased EEPROM reads as 0xff
+        bytes.resize(EEPROM_SIZE, 0xff);
+        VirtualEeprom {
+            bytes: Rc::new(RefCell::new(bytes)),
+            path: None,
+        }
+    }
+
+    /// Starts out holding whatever's in the file at `path`, and writes it back
+    /// there after every change.
+    pub fn with_file(path: PathBuf) -> VirtualEeprom {
+        let bytes = fs::read(&path).unwrap_or_default();
+        VirtualEeprom {
+            path: Some(path),
+            ..VirtualEeprom::new(&bytes)
+        }
+    }
+
+    /// Everything in the EEPROM now.
+    pub fn contents(&self) -> Vec<u8> {
+        self.bytes.borrow().clone()
+    }
+}
+
+impl Eeprom for VirtualEeprom {
+    fn read_byte(&self, offset: u16) -> u8 {
+        self.bytes
+            .borrow()
+            .get(offset as usize)
+            .copied()
+            .unwrap_or(0xff)
+    }
+
+    fn write_byte(&mut self, offset: u16, byte: u8) {
+        let mut bytes = self.bytes.borrow_mut();
+        if let Some(cell) = bytes.get_mut(offset as usize) {
+            *cell = byte;
+        }
+        if let Some(path) = &self.path {
+            // Not much can be done about it, and the clock carries on anyway
+            let _ = fs::write(path, &*bytes);
+        }
+    }
+}
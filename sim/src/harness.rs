@@ -0,0 +1,428 @@
+//! Runs the clock against a script of button presses in virtual time, so tests
+//! can check exactly what it shows and sends after each step.
+//!
+//! Virtual time only moves when the clock waits, so a run always goes the same
+//! way. The harness plays the website too, answering the handshake so that the
+//! clock sends its times over serial, and sending whatever the script says.
+//!
+//! # Usage
+//! ```
+//! use open_chess_clock_sim::harness::{Input, Script};
+//!
+//! let frames = Script::new()
+//!     .tap(4_000, Input::Start) // Pick the first preset
+//!     .tap(5_000, Input::Down) // Start P1's clock
+//!     .run();
+//! assert_eq!(frames[1].lines[0], " P1/P2 to begin ");
+//! ```
+
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use open_chess_clock_core::{
+    countdown::Turn,
+    display::{CharLcd, ClockDisplay, Geometry, Oled, SegmentClocks},
+    error::RuntimeError,
+    hal::SerialPort,
+    serial::{
+        Identity, SerialHandler, SerialMsg, CAPABILITY_INCREMENTS, CAPABILITY_MOVE_COUNTERS,
+        CAPABILITY_STAGES, PROTOCOL_VERSION,
+    },
+    storage::Storage,
+};
+
+pub use crate::hardware::Input;
+use crate::{
+    hardware::{
+        Hardware, VirtualButton, VirtualBuzzer, VirtualEeprom, VirtualLcd, VirtualOled,
+        VirtualSegments, VirtualSerial, VirtualTime,
+    },
+    lcd::{Ddram, LINES},
+    oled::Gddram,
+    segments::SegmentPair,
+};
+
+/// How long a tapped button is held down for, in ms.
+pub const TAP_MS: u32 = 100;
+/// How long the clock keeps running after the last step, in ms.
+pub const SETTLE_MS: u32 = 100;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Event {
+    Set(Input, bool),
+    /// A message from the website
+    Send(SerialMsg),
+    /// Bytes arriving from the website, which needn't make a whole message
+    SendBytes(&'static [u8]),
+    Frame,
+}
+
+/// How things stood at some point in a run.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    /// The virtual time in ms
+    pub at: u32,
+    /// What the LCD showed, row by row, or the OLED drawn with half blocks
+    pub lines: Vec<String>,
+    /// Everything in the LCD's memory, including what's off the edge
+    pub ddram: [String; LINES],
+    /// What the segment displays facing P1 and P2 showed, if there are any
+    pub segments: Option<[String; 2]>,
+    /// Everything which was sent over serial since the previous frame
+    pub sent: Vec<SerialMsg>,
+    pub buzzing: bool,
+}
+
+/// Button presses and releases at set times, counted in virtual ms from when
+/// the clock is switched on.
+pub struct Script {
+    events: Vec<(u32, Event)>,
+    eeprom: Vec<u8>,
+    geometry: Geometry,
+    oled: bool,
+    segments: bool,
+    unplugged: bool,
+}
+
+impl Script {
+    pub fn new() -> Script {
+        Script {
+            events: vec![],
+            // Erased, like a fresh chip
+            eeprom: vec![],
+            geometry: Geometry::LCD_16X2,
+            oled: false,
+            segments: false,
+            unplugged: false,
+        }
+    }
+
+    /// Starts the clock with the given EEPROM contents, such as those left by
+    /// a previous run.
+    pub fn with_eeprom(mut self, eeprom: &[u8]) -> Script {
+        self.eeprom = eeprom.to_vec();
+        self
+    }
+
+    /// Runs the clock on an LCD of `geometry` rather than 16x2.
+    pub fn with_geometry(mut self, geometry: Geometry) -> Script {
+        self.geometry = geometry;
+        self
+    }
+
+    /// Runs the clock on a 128x64 OLED rather than an LCD.
+    pub fn with_oled(mut self) -> Script {
+        self.oled = true;
+        self
+    }
+
+    /// Adds a seven-segment display facing each player, with the LCD or OLED
+    /// showing everything else.
+    pub fn with_segments(mut self) -> Script {
+        self.segments = true;
+        self
+    }
+
+    /// Switches the clock on with nothing at the other end of the cable, so
+    /// nobody answers its handshake until the script sends something.
+    pub fn unplugged(mut self) -> Script {
+        self.unplugged = true;
+        self
+    }
+
+    /// Presses `input` at `at` and keeps it held down.
+    pub fn press(self, at: u32, input: Input) -> Script {
+        self.step(at, &[(0, Event::Set(input, true))])
+    }
+
+    /// Lets go of `input` at `at`.
+    pub fn release(self, at: u32, input: Input) -> Script {
+        self.step(at, &[(0, Event::Set(input, false))])
+    }
+
+    /// Presses `input` at `at` and lets go of it [`TAP_MS`] later.
+    pub fn tap(self, at: u32, input: Input) -> Script {
+        self.step(
+            at,
+            &[
+                (0, Event::Set(input, true)),
+                (TAP_MS, Event::Set(input, false)),
+            ],
+        )
+    }
+
+    /// Has the website send `msg` at `at`.
+    pub fn send(self, at: u32, msg: SerialMsg) -> Script {
+        self.step(at, &[(0, Event::Send(msg))])
+    }
+
+    /// Has the website send `bytes` at `at`, as they'd come off the wire.
+    pub fn send_bytes(self, at: u32, bytes: &'static [u8]) -> Script {
+        self.step(at, &[(0, Event::SendBytes(bytes))])
+    }
+
+    /// Does nothing at `at` besides taking a frame.
+    pub fn wait(self, at: u32) -> Script {
+        self.step(at, &[])
+    }
+
+    fn step(mut self, at: u32, events: &[(u32, Event)]) -> Script {
+        assert!(
+            self.events
+                .iter()
+                .rfind(|&&(_, event)| event == Event::Frame)
+                .is_none_or(|&(last, _)| last <= at),
+            "steps have to be in order"
+        );
+        self.events.push((at, Event::Frame));
+        for &(after, event) in events {
+            self.events.push((at + after, event));
+        }
+        self
+    }
+
+    /// Runs the clock through the script. Returns a frame from just before each
+    /// step, and one from [`SETTLE_MS`] after the last, so `frames[i + 1]` is
+    /// how things stood after step `i`.
+    pub fn run(self) -> Vec<Frame> {
+        self.run_with_eeprom().0
+    }
+
+    /// Like [`Script::run`], also returning what was left in the EEPROM.
+    pub fn run_with_eeprom(mut self) -> (Vec<Frame>, Vec<u8>) {
+        let end = self.events.last().map_or(0, |&(at, _)| at) + SETTLE_MS;
+        self.events.push((end, Event::Frame));
+        // Taps can overlap later steps, so line everything up by time
+        self.events.sort_by_key(|&(at, _)| at);
+
+        let board = Rc::new(RefCell::new(Board {
+            now: 0,
+            events: self.events.into(),
+            pressed: [false; 3],
+            buzzing: false,
+            lcd: Ddram::new(),
+            geometry: self.geometry,
+            oled: self.oled.then(Gddram::new),
+            segments: self.segments.then(SegmentPair::new),
+            // The website answers the handshake straight away
+            incoming: if self.unplugged {
+                VecDeque::new()
+            } else {
+                handshake_response()
+            },
+            outgoing: VecDeque::new(),
+            frames: vec![],
+            finished: false,
+        }));
+        let eeprom = VirtualEeprom::new(&self.eeprom);
+        let err = if self.oled {
+            run_on(
+                &board,
+                eeprom.clone(),
+                Oled::new(VirtualOled(board.clone())),
+            )
+        } else {
+            let display = CharLcd::with_geometry(VirtualLcd(board.clone()), self.geometry);
+            run_on(&board, eeprom.clone(), display)
+        };
+        let board = board.borrow();
+        // The buttons stop the clock once the script is done
+        if !board.finished || err != RuntimeError::PinReadError {
+            panic!("clock stopped at {}ms with {}", board.now, err.name());
+        }
+        (board.frames.clone(), eeprom.contents())
+    }
+}
+
+/// Runs the clock on `display`, along with the segment displays if there are
+/// any, until it stops.
+fn run_on<D: ClockDisplay>(
+    board: &Rc<RefCell<Board>>,
+    eeprom: VirtualEeprom,
+    mut display: D,
+) -> RuntimeError {
+    if board.borrow().segments.is_some() {
+        let segments = VirtualSegments(board.clone());
+        run_with(board, eeprom, &mut SegmentClocks::new(segments, display))
+    } else {
+        run_with(board, eeprom, &mut display)
+    }
+}
+
+fn run_with<D: ClockDisplay>(
+    board: &Rc<RefCell<Board>>,
+    eeprom: VirtualEeprom,
+    display: &mut D,
+) -> RuntimeError {
+    let Err(err) = open_chess_clock_core::run(
+        VirtualButton(board.clone(), Input::Down),
+        VirtualButton(board.clone(), Input::Up),
+        VirtualButton(board.clone(), Input::Start),
+        VirtualBuzzer(board.clone()),
+        SerialHandler::new(VirtualSerial(board.clone())),
+        Storage::new(eeprom),
+        &mut VirtualTime(board.clone()),
+        display,
+        "test",
+    );
+    err
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Script::new()
+    }
+}
+
+/// Everything the virtual hardware shares, with time only moving when the
+/// clock waits.
+struct Board {
+    now: u32,
+    events: VecDeque<(u32, Event)>,
+    pressed: [bool; 3],
+    buzzing: bool,
+    lcd: Ddram,
+    geometry: Geometry,
+    /// The OLED, if the clock is running on one instead of the LCD
+    oled: Option<Gddram>,
+    segments: Option<SegmentPair>,
+    /// Bytes waiting for the clock to read
+    incoming: VecDeque<u8>,
+    /// Bytes sent by the clock since the last frame
+    outgoing: VecDeque<u8>,
+    frames: Vec<Frame>,
+    finished: bool,
+}
+
+impl Board {
+    /// Moves time on by `ms`, going through any events on the way.
+    fn advance(&mut self, ms: u16) {
+        self.now += ms as u32;
+        while let Some(&(at, event)) = self.events.front() {
+            if at > self.now {
+                break;
+            }
+            self.events.pop_front();
+            match event {
+                Event::Set(input, pressed) => self.pressed[input as usize] = pressed,
+                Event::Send(msg) => msg.send(&mut Recorder(&mut self.incoming)),
+                Event::SendBytes(bytes) => self.incoming.extend(bytes),
+                Event::Frame => {
+                    let frame = self.frame(at);
+                    self.frames.push(frame);
+                }
+            }
+        }
+        if self.events.is_empty() {
+            self.finished = true;
+        }
+    }
+
+    fn frame(&mut self, at: u32) -> Frame {
+        let mut sent = vec![];
+        let bytes = Rc::new(RefCell::new(self.outgoing.drain(..).collect()));
+        let mut decoder = SerialHandler::new(Replay(bytes.clone()));
+        while !bytes.borrow().is_empty() {
+            if let Some(msg) = decoder.read() {
+                sent.push(msg);
+            }
+        }
+        Frame {
+            at,
+            lines: match &self.oled {
+                Some(oled) => oled.rows(),
+                None => self.lcd.rows(self.geometry),
+            },
+            ddram: [self.lcd.line(0), self.lcd.line(1)],
+            segments: self
+                .segments
+                .as_ref()
+                .map(|segments| [segments.line(&Turn::P1), segments.line(&Turn::P2)]),
+            sent,
+            buzzing: self.buzzing,
+        }
+    }
+}
+
+/// The frame the website answers the clock's handshake with, agreeing to be
+/// its slave.
+fn handshake_response() -> VecDeque<u8> {
+    let mut bytes = VecDeque::new();
+    SerialMsg::HandshakeResponse {
+        selected_mode: 0x0002,
+        sender: website(),
+    }
+    .send(&mut Recorder(&mut bytes));
+    bytes
+}
+
+/// The website as it describes itself, which understands everything the clock
+/// sends.
+pub fn website() -> Identity {
+    Identity {
+        protocol_version: PROTOCOL_VERSION,
+        capabilities: CAPABILITY_INCREMENTS | CAPABILITY_MOVE_COUNTERS | CAPABILITY_STAGES,
+        version: 0,
+    }
+}
+
+/// Bytes sent by the clock, played back to be decoded.
+struct Replay(Rc<RefCell<VecDeque<u8>>>);
+
+impl SerialPort for Replay {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.0.borrow_mut().pop_front()
+    }
+
+    fn write_byte(&mut self, _byte: u8) {}
+}
+
+/// Keeps the bytes written to it.
+struct Recorder<'a>(&'a mut VecDeque<u8>);
+
+impl SerialPort for Recorder<'_> {
+    fn read_byte(&mut self) -> Option<u8> {
+        None
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.0.push_back(byte);
+    }
+}
+
+impl Hardware for Board {
+    fn millis(&self) -> u32 {
+        self.now
+    }
+
+    fn delay_ms(&mut self, ms: u16) {
+        self.advance(ms);
+    }
+
+    fn is_pressed(&mut self, input: Input) -> Option<bool> {
+        (!self.finished).then_some(self.pressed[input as usize])
+    }
+
+    fn set_buzzing(&mut self, buzzing: bool) {
+        self.buzzing = buzzing;
+    }
+
+    fn lcd(&mut self) -> &mut Ddram {
+        &mut self.lcd
+    }
+
+    fn oled(&mut self) -> Option<&mut Gddram> {
+        self.oled.as_mut()
+    }
+
+    fn segments(&mut self) -> Option<&mut SegmentPair> {
+        self.segments.as_mut()
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        self.incoming.pop_front()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.outgoing.push_back(byte);
+    }
+}
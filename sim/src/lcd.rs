@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+
+use open_chess_clock_core::{
+    display::Geometry,
+    hal::{uWrite, Lcd},
+    LCD_LINE_LENGTH,
+};
+
+/// The number of lines of the LCD.
+pub const LINES: usize = 2;
+/// The number of characters the HD44780 keeps for each line.
+pub const DDRAM_COLUMNS: usize = LCD_LINE_LENGTH as usize;
+/// The number of characters of each line which a 16x2 LCD actually shows.
+pub const VISIBLE_COLUMNS: usize = 16;
+
+/// Returned when the cursor is moved somewhere the HD44780 has no memory.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct InvalidPosition(pub u8);
+
+/// The display memory of an HD44780, which holds 40 characters for each line
+/// of which only the first 16 are shown. Like the real thing, the cursor moves
+/// on to the next line after the end of one.
+///
+/// The eight custom characters are kept too, and shown as whichever block
+/// character looks most like them.
+pub struct Ddram {
+    cells: [[u8; DDRAM_COLUMNS]; LINES],
+    cursor: usize,
+    cgram: [[u8; 8]; 8],
+}
+
+impl Ddram {
+    pub fn new() -> Ddram {
+        Ddram {
+            cells: [[b' '; DDRAM_COLUMNS]; LINES],
+            cursor: 0,
+            cgram: [[0; 8]; 8],
+        }
+    }
+
+    /// Everything held for `line`, including what's off the edge of the screen.
+    pub fn line(&self, line: usize) -> String {
+        self.cells[line]
+            .iter()
+            .map(|&byte| match byte {
+                0..=7 => glyph(&self.cgram[byte as usize]),
+                // The arrows where ASCII has `~` and DEL
+                0x7e => '\u{2192}',
+                0x7f => '\u{2190}',
+                // Only ASCII is ever written otherwise
+                _ => byte as char,
+            })
+            .collect()
+    }
+
+    /// What an LCD of `geometry` shows, row by row.
+    pub fn rows(&self, geometry: Geometry) -> Vec<String> {
+        (0..geometry.rows)
+            .map(|row| {
+                let start = geometry.position(row, 0) as usize;
+                self.line(start / DDRAM_COLUMNS)
+                    .chars()
+                    .skip(start % DDRAM_COLUMNS)
+                    .take(geometry.columns as usize)
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Picks a character which looks like the custom character `rows`: a block for
+/// bars across the top, bottom or both, a box-drawing character for lines
+/// along the edges, a dot for something small in the middle and an arrow for
+/// anything heavier on one side.
+fn glyph(rows: &[u8; 8]) -> char {
+    // Bit i is set if row i is a solid bar, or `None` if any row is partly lit
+    let bars = rows
+        .iter()
+        .enumerate()
+        .try_fold(0u8, |bars, (i, row)| match row & 0x1f {
+            0x1f => Some(bars | 1 << i),
+            0 => Some(bars),
+            _ => None,
+        });
+    match bars {
+        Some(0) => ' ',
+        Some(0b1111_1111) => '\u{2588}',
+        Some(0b0000_0011) => '\u{2580}',
+        Some(0b1100_0000) => '\u{2584}',
+        Some(0b1100_0011) => '=',
+        _ if rows[1..7].iter().all(|row| row & 0x1f & !0x11 == 0) => {
+            let side = |bit: u8| rows[1..7].iter().all(|row| row & bit != 0);
+            match (
+                rows[0] & 0x1f == 0x1f,
+                side(0x10),
+                side(0x01),
+                rows[7] & 0x1f == 0x1f,
+            ) {
+                (true, true, true, true) => '\u{25a1}',
+                (true, true, true, false) => '\u{2293}',
+                (false, true, true, true) => '\u{2294}',
+                (true, true, false, true) => '\u{228f}',
+                (true, false, true, true) => '\u{2290}',
+                (true, false, true, false) => '\u{2510}',
+                (false, false, true, true) => '\u{2518}',
+                _ => '\u{2595}',
+            }
+        }
+        _ => {
+            let left: u32 = rows.iter().map(|row| (row >> 3 & 0b11).count_ones()).sum();
+            let right: u32 = rows.iter().map(|row| (row & 0b11).count_ones()).sum();
+            match left.cmp(&right) {
+                Ordering::Greater => '\u{25b6}',
+                Ordering::Less => '\u{25c0}',
+                Ordering::Equal => '\u{2022}',
+            }
+        }
+    }
+}
+
+impl Default for Ddram {
+    fn default() -> Self {
+        Ddram::new()
+    }
+}
+
+impl uWrite for Ddram {
+    type Error = InvalidPosition;
+
+    fn write_str(&mut self, s: &str) -> Result<(), InvalidPosition> {
+        for byte in s.bytes() {
+            // The HD44780 only knows ASCII, more or less
+            let byte = if byte.is_ascii() { byte } else { b'?' };
+            self.cells[self.cursor / DDRAM_COLUMNS][self.cursor % DDRAM_COLUMNS] = byte;
+            self.cursor = (self.cursor + 1) % (DDRAM_COLUMNS * LINES);
+        }
+        Ok(())
+    }
+}
+
+impl Lcd for Ddram {
+    fn clear(&mut self) -> Result<(), InvalidPosition> {
+        // Clearing leaves the custom characters as they were
+        *self = Ddram {
+            cgram: self.cgram,
+            ..Ddram::new()
+        };
+        Ok(())
+    }
+
+    fn set_cursor_pos(&mut self, position: u8) -> Result<(), InvalidPosition> {
+        if position as usize >= DDRAM_COLUMNS * LINES {
+            return Err(InvalidPosition(position));
+        }
+        self.cursor = position as usize;
+        Ok(())
+    }
+
+    fn define_char(&mut self, index: u8, rows: &[u8; 8]) -> Result<(), InvalidPosition> {
+        self.cgram[index as usize % 8] = *rows;
+        Ok(())
+    }
+}
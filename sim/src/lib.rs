@@ -0,0 +1,7 @@
+//! Stand-ins for the clock's hardware which run on a computer.
+
+pub mod hardware;
+pub mod harness;
+pub mod lcd;
+pub mod oled;
+pub mod segments;
@@ -0,0 +1,127 @@
+//! Runs the clock in a terminal, so changes to the screens can be tried out
+//! without flashing a board.
+
+use std::{env, path::PathBuf, process};
+
+use board::{Panel, SharedPanel};
+use open_chess_clock_core::{
+    display::{CharLcd, ClockDisplay, Geometry, Oled, SegmentClocks},
+    error::RuntimeError,
+    serial::SerialHandler,
+    storage::Storage,
+};
+use open_chess_clock_sim::hardware::{
+    Input, VirtualButton, VirtualBuzzer, VirtualEeprom, VirtualLcd, VirtualOled, VirtualSegments,
+    VirtualSerial, VirtualTime,
+};
+use terminal::Terminal;
+
+mod board;
+mod terminal;
+
+const USAGE: &str = "\
+Usage: open-chess-clock-sim [--speed N] [--eeprom FILE] [--lcd SIZE | --oled]
+                            [--segments]
+
+  --speed N      run the clock N times faster than real time (1 to 64)
+  --eeprom FILE  keep the EEPROM in FILE, so presets are still there next time
+  --lcd SIZE     the size of the LCD, 16x2 (the default) or 20x4
+  --oled         show a 128x64 OLED instead of an LCD
+  --segments     add a seven-segment display facing each player";
+
+fn main() {
+    let mut speed = 1;
+    let mut eeprom = None;
+    let mut geometry = Geometry::LCD_16X2;
+    let mut oled = false;
+    let mut segments = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--oled" => oled = true,
+            "--segments" => segments = true,
+            _ => match (arg.as_str(), args.next()) {
+                ("--speed", Some(value)) => match value.parse() {
+                    Ok(value) => speed = value,
+                    Err(_) => exit_with_usage(),
+                },
+                ("--eeprom", Some(path)) => eeprom = Some(PathBuf::from(path)),
+                ("--lcd", Some(size)) => match size.as_str() {
+                    "16x2" => geometry = Geometry::LCD_16X2,
+                    "20x4" => geometry = Geometry::LCD_20X4,
+                    _ => exit_with_usage(),
+                },
+                _ => exit_with_usage(),
+            },
+        }
+    }
+
+    let terminal = match Terminal::enter() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            eprintln!("{err}");
+            process::exit(1);
+        }
+    };
+    let keys = terminal::read_keys();
+    let panel = Panel::new(terminal, keys, geometry, oled, segments, speed);
+
+    let eeprom = match eeprom {
+        Some(path) => VirtualEeprom::with_file(path),
+        None => VirtualEeprom::new(&[]),
+    };
+    let err = if oled {
+        run_on(
+            &panel,
+            eeprom,
+            segments,
+            Oled::new(VirtualOled(panel.clone())),
+        )
+    } else {
+        let display = CharLcd::with_geometry(VirtualLcd(panel.clone()), geometry);
+        run_on(&panel, eeprom, segments, display)
+    };
+    panel.borrow_mut().terminal.restore();
+    eprintln!("fatal error: {}", err.name());
+    process::exit(1);
+}
+
+/// Runs the clock on `display`, along with the segment displays if `segments`
+/// is set, until it stops.
+fn run_on<D: ClockDisplay>(
+    panel: &SharedPanel,
+    eeprom: VirtualEeprom,
+    segments: bool,
+    mut display: D,
+) -> RuntimeError {
+    if segments {
+        let segments = VirtualSegments(panel.clone());
+        run_with(panel, eeprom, &mut SegmentClocks::new(segments, display))
+    } else {
+        run_with(panel, eeprom, &mut display)
+    }
+}
+
+fn run_with<D: ClockDisplay>(
+    panel: &SharedPanel,
+    eeprom: VirtualEeprom,
+    display: &mut D,
+) -> RuntimeError {
+    let Err(err) = open_chess_clock_core::run(
+        VirtualButton(panel.clone(), Input::Down),
+        VirtualButton(panel.clone(), Input::Up),
+        VirtualButton(panel.clone(), Input::Start),
+        VirtualBuzzer(panel.clone()),
+        SerialHandler::new(VirtualSerial(panel.clone())),
+        Storage::new(eeprom),
+        &mut VirtualTime(panel.clone()),
+        display,
+        env!("CARGO_PKG_VERSION"),
+    );
+    err
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{USAGE}");
+    process::exit(2);
+}
@@ -0,0 +1,67 @@
+use open_chess_clock_core::hal::PagedDisplay;
+
+/// The number of columns of pixels.
+pub const WIDTH: usize = 128;
+/// The number of strips of eight rows of pixels.
+pub const PAGES: usize = 8;
+
+/// Returned when something's written past the edge of the display, with the
+/// page and column it started at.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct InvalidPage(pub u8, pub u8);
+
+/// The display memory of an SSD1306, which holds every pixel of a 128x64 OLED.
+pub struct Gddram {
+    pages: [[u8; WIDTH]; PAGES],
+}
+
+impl Gddram {
+    pub fn new() -> Gddram {
+        Gddram {
+            pages: [[0; WIDTH]; PAGES],
+        }
+    }
+
+    /// Whether the pixel at `x` from the left and `y` from the top is lit.
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pages[y / 8][x] >> (y % 8) & 1 != 0
+    }
+
+    /// The display drawn with half blocks, two rows of pixels to a line.
+    pub fn rows(&self) -> Vec<String> {
+        (0..PAGES * 4)
+            .map(|row| {
+                (0..WIDTH)
+                    .map(|x| {
+                        let (top, bottom) = (self.pixel(x, row * 2), self.pixel(x, row * 2 + 1));
+                        match (top, bottom) {
+                            (false, false) => ' ',
+                            (true, false) => '\u{2580}',
+                            (false, true) => '\u{2584}',
+                            (true, true) => '\u{2588}',
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl Default for Gddram {
+    fn default() -> Self {
+        Gddram::new()
+    }
+}
+
+impl PagedDisplay for Gddram {
+    type Error = InvalidPage;
+
+    fn write_page(&mut self, page: u8, column: u8, columns: &[u8]) -> Result<(), InvalidPage> {
+        let start = column as usize;
+        if page as usize >= PAGES || start + columns.len() > WIDTH {
+            return Err(InvalidPage(page, column));
+        }
+        self.pages[page as usize][start..start + columns.len()].copy_from_slice(columns);
+        Ok(())
+    }
+}
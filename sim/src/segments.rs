@@ -0,0 +1,77 @@
+use std::convert::Infallible;
+
+use open_chess_clock_core::{countdown::Turn, hal::SegmentDisplays};
+
+/// The characters which the segment displays show, with their segments.
+const CHARACTERS: [(u8, char); 15] = [
+    (0x00, ' '),
+    (0x3f, '0'),
+    (0x06, '1'),
+    (0x5b, '2'),
+    (0x4f, '3'),
+    (0x66, '4'),
+    (0x6d, '5'),
+    (0x7d, '6'),
+    (0x07, '7'),
+    (0x7f, '8'),
+    (0x6f, '9'),
+    (0x40, '-'),
+    (0x74, 'h'),
+    (0x79, 'E'),
+    (0x50, 'r'),
+];
+const POINT: u8 = 0x80;
+
+/// A four-digit seven-segment display facing each player, which remembers
+/// what it was last told to show.
+#[derive(Debug, Default)]
+pub struct SegmentPair {
+    digits: [[u8; 4]; 2],
+    colons: [bool; 2],
+}
+
+impl SegmentPair {
+    pub fn new() -> SegmentPair {
+        SegmentPair::default()
+    }
+
+    /// What `player`'s display shows, with a `.` after any digit with its
+    /// decimal point lit and the colon as `:` if it's lit.
+    pub fn line(&self, player: &Turn) -> String {
+        let side = player_index(player);
+        let mut line = String::new();
+        for (i, &segments) in self.digits[side].iter().enumerate() {
+            if i == 2 {
+                line.push(if self.colons[side] { ':' } else { ' ' });
+            }
+            line.push(
+                CHARACTERS
+                    .iter()
+                    .find(|&&(character, _)| character == segments & !POINT)
+                    .map_or('?', |&(_, character)| character),
+            );
+            if segments & POINT != 0 {
+                line.push('.');
+            }
+        }
+        line
+    }
+}
+
+impl SegmentDisplays for SegmentPair {
+    type Error = Infallible;
+
+    fn show(&mut self, player: &Turn, digits: &[u8; 4], colon: bool) -> Result<(), Infallible> {
+        let side = player_index(player);
+        self.digits[side] = *digits;
+        self.colons[side] = colon;
+        Ok(())
+    }
+}
+
+fn player_index(player: &Turn) -> usize {
+    match player {
+        Turn::P1 => 0,
+        Turn::P2 => 1,
+    }
+}
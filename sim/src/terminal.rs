@@ -0,0 +1,156 @@
+//! Reading keys from and drawing the clock in the terminal, with nothing more
+//! than `stty` and ANSI escape codes.
+
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+/// What a key press asks the simulator to do.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Key {
+    /// Press and release one of the buttons: 0 is down, 1 is start and 2 is up.
+    Tap(usize),
+    /// Hold one of the buttons down, or let go of it if it's already held.
+    Hold(usize),
+    Faster,
+    Slower,
+    Quit,
+}
+
+/// The terminal while the simulator is using it. Puts everything back how it
+/// was when dropped.
+pub struct Terminal {
+    /// The settings from before, as printed by `stty -g`
+    saved: Option<String>,
+}
+
+impl Terminal {
+    /// Stops the terminal from waiting for Enter, echoing keys or handling
+    /// Ctrl+C itself, and hides the cursor.
+    pub fn enter() -> io::Result<Terminal> {
+        let saved = String::from_utf8_lossy(&stty(&["-g"])?).trim().to_owned();
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        print!("\x1b[2J\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(Terminal { saved: Some(saved) })
+    }
+
+    /// Puts the terminal back how it was. Does nothing the second time.
+    pub fn restore(&mut self) {
+        if let Some(saved) = self.saved.take() {
+            let _ = stty(&[&saved]);
+            println!("\x1b[?25h");
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed; is this a terminal?"));
+    }
+    Ok(output.stdout)
+}
+
+/// Reads keys on another thread, since there's no way to check for one without
+/// waiting.
+pub fn read_keys() -> Receiver<Key> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut escape = 0;
+        for byte in io::stdin().lock().bytes() {
+            let Ok(byte) = byte else { break };
+            // Arrow keys come as ESC [ C and ESC [ D
+            let key = match (escape, byte) {
+                (0, 0x1b) | (1, b'[') => {
+                    escape += 1;
+                    continue;
+                }
+                (2, b'D') => Some(Key::Tap(0)),
+                (2, b'C') => Some(Key::Tap(2)),
+                (2, _) => None,
+                (_, b'a') => Some(Key::Tap(0)),
+                (_, b's' | b' ') => Some(Key::Tap(1)),
+                (_, b'd') => Some(Key::Tap(2)),
+                (_, b'A') => Some(Key::Hold(0)),
+                (_, b'S') => Some(Key::Hold(1)),
+                (_, b'D') => Some(Key::Hold(2)),
+                (_, b'+' | b'=') => Some(Key::Faster),
+                (_, b'-') => Some(Key::Slower),
+                // Ctrl+C and Ctrl+D as well, since the terminal won't handle them
+                (_, b'q' | 0x03 | 0x04) => Some(Key::Quit),
+                _ => None,
+            };
+            escape = 0;
+            if let Some(key) = key {
+                if sender.send(key).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// Draws the display's `rows`, any segment displays, the buzzer and the keys
+/// from the top left of the terminal.
+pub fn draw(
+    rows: &[String],
+    segments: Option<[String; 2]>,
+    buzzing: bool,
+    held: [bool; 3],
+    millis: u32,
+    speed: u32,
+) {
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    let border = "\u{2500}".repeat(width);
+    let mut frame = format!("\x1b[H\n  \u{250c}{border}\u{2510}\n");
+    for row in rows {
+        frame += &format!("  \u{2502}{row}\u{2502}\n");
+    }
+    frame += &format!("  \u{2514}{border}\u{2518}\n\n");
+    if let Some([p1, p2]) = segments {
+        frame += &format!("  P1 [{p1:7}]  P2 [{p2:7}]\n\n");
+    }
+    frame += if buzzing {
+        "  \x1b[7m BUZZ \x1b[0m\n"
+    } else {
+        "        \n"
+    };
+    let secs = millis / 1000;
+    frame += &format!(
+        "  Running for {}:{:02}:{:02}.{:03} at {}x    \n\n",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        millis % 1000,
+        speed
+    );
+    let names = [
+        "a/\u{2190}  down (P1)",
+        "s/space  start",
+        "d/\u{2192}  up (P2)",
+    ];
+    for (name, held) in names.iter().zip(held) {
+        frame += &format!("  {name:16}{}\n", if held { " held" } else { "     " });
+    }
+    frame += "\n  A/S/D  hold a button down until pressed again\n";
+    frame += "  +/-    change the speed\n";
+    frame += "  q      quit\n";
+    print!("{frame}");
+    let _ = io::stdout().flush();
+}
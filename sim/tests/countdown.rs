@@ -0,0 +1,698 @@
+use open_chess_clock_core::serial::{Identity, SerialMsg, CAPABILITY_RECONNECTS};
+use open_chess_clock_sim::harness::{website, Input, Script};
+
+/// Picks Bullet 1+0, the first preset on a fresh clock, and starts P1's clock
+/// at 5s.
+fn bullet() -> Script {
+    Script::new()
+        .tap(4_000, Input::Start)
+        .tap(5_000, Input::Down)
+}
+
+#[test]
+fn moves_charge_the_running_player() {
+    let frames = bullet()
+        .tap(17_300, Input::Down)
+        .tap(20_000, Input::Up)
+        .run();
+    assert_eq!(frames[0].lines, ["     Preset     ", "   Bullet 1+0   "]);
+    assert_eq!(
+        frames[0].sent,
+        [
+            SerialMsg::Sync {
+                p1_time: 60_000,
+                p2_time: 60_000,
+            },
+            SerialMsg::Mode { mode: 0 },
+            SerialMsg::Increment {
+                p1_increment: 0,
+                p2_increment: 0,
+            },
+        ]
+    );
+    assert_eq!(frames[1].lines, [" P1/P2 to begin ", "0:01:00  0:01:00"]);
+    assert_eq!(frames[2].lines, ["[P1]   <<    P2 ", "0:00:48  0:01:00"]);
+    assert_eq!(
+        frames[2].sent,
+        [SerialMsg::StartP1 {
+            p2_time: 60_000,
+            move_number: 1,
+        }]
+    );
+    // Both presses take 15ms to get through the debouncing, so P1 ran from
+    // 5.015s to 17.315s
+    assert_eq!(frames[3].lines, [" P1    >>   [P2]", "0:00:48  0:00:58"]);
+    assert_eq!(
+        frames[3].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 47_705,
+            move_number: 1,
+        }]
+    );
+    assert_eq!(frames[4].lines, ["[P1]   <<    P2 ", "0:00:48  0:00:58"]);
+    assert_eq!(
+        frames[4].sent,
+        [SerialMsg::StartP1 {
+            p2_time: 57_305,
+            move_number: 2,
+        }]
+    );
+}
+
+#[test]
+fn pausing_keeps_both_times() {
+    let frames = bullet()
+        .tap(15_000, Input::Start)
+        .wait(60_000)
+        .tap(70_000, Input::Up)
+        .tap(75_000, Input::Start)
+        .run();
+    assert_eq!(frames[2].lines, ["[P1]   <<    P2 ", "0:00:51  0:01:00"]);
+    // START pauses once it's let go of, so P1 ran until 15.115s
+    assert_eq!(frames[3].lines[1], "0:00:50  0:01:00");
+    assert_eq!(frames[3].sent, [SerialMsg::Pause { time: 49_905 }]);
+    // Nothing runs down while paused, however long it's for
+    assert_eq!(frames[4].lines[1], "0:00:50  0:01:00");
+    assert_eq!(frames[4].sent, []);
+    // Resuming with P2 starts P2's clock, with P1's left as it was
+    assert_eq!(frames[5].lines, [" P1    >>   [P2]", "0:00:50  0:00:56"]);
+    assert_eq!(
+        frames[5].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 49_905,
+            move_number: 1,
+        }]
+    );
+    assert_eq!(frames[6].lines[1], "0:00:50  0:00:55");
+    assert_eq!(frames[6].sent, [SerialMsg::Pause { time: 54_905 }]);
+}
+
+#[test]
+fn stopping_from_pause_goes_back_to_set_up() {
+    let frames = bullet()
+        .tap(15_000, Input::Start)
+        .tap(16_000, Input::Start)
+        .wait(16_500)
+        .run();
+    assert_eq!(frames[3].lines[0], " P1  Paused  P2 ");
+    assert_eq!(frames[4].lines[0], "     Preset     ");
+}
+
+#[test]
+fn running_out_of_time_finishes_the_game() {
+    let frames = bullet()
+        .wait(64_500)
+        .wait(65_100)
+        .wait(70_000)
+        .tap(80_000, Input::Start)
+        .run();
+    assert_eq!(frames[2].lines, ["[P1]   <<    P2 ", "0:00:01  0:01:00"]);
+    assert_eq!(frames[3].lines, ["[P1]  Time's up!", "0:00:00  0:01:00"]);
+    assert_eq!(frames[3].sent, [SerialMsg::P1Finish]);
+    assert!(frames[3].buzzing);
+    // The buzzer stops after a while, but the result stays until START
+    assert_eq!(frames[4].lines[1], "0:00:00  0:01:00");
+    assert!(!frames[4].buzzing);
+    assert_eq!(frames[6].lines[0], "     Preset     ");
+}
+
+#[test]
+fn fischer_increment_is_added_after_each_move() {
+    // Blitz 3+2 is the next preset along
+    let frames = Script::new()
+        .tap(4_000, Input::Up)
+        .tap(5_000, Input::Start)
+        .tap(6_000, Input::Down)
+        .tap(16_000, Input::Down)
+        .run();
+    assert_eq!(frames[2].lines[1], "0:03:00  0:03:00");
+    assert_eq!(
+        frames[4].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 172_005,
+            move_number: 1,
+        }]
+    );
+    assert_eq!(frames[4].lines[1], "0:02:53  0:03:00");
+}
+
+/// Taps through Custom on a fresh clock, 300ms apart from 4s on: `mins`
+/// minutes each, the mode `mode_steps` along from Fischer with `increment`
+/// seconds each, and the overtime `overtime_steps` along from none with its
+/// usual settings. Returns the script and when it's done.
+fn custom(mins: u32, mode_steps: u32, increment: u32, overtime_steps: u32) -> (Script, u32) {
+    let mut script = Script::new();
+    let mut at = 4_000;
+    let mut taps = |script: Script, input, count| {
+        (0..count).fold(script, |script, _| {
+            at += 300;
+            script.tap(at - 300, input)
+        })
+    };
+    // Custom is just before Bullet 1+0
+    script = taps(script, Input::Down, 1);
+    script = taps(script, Input::Start, 1);
+    script = taps(script, Input::Up, mins);
+    script = taps(script, Input::Start, 2);
+    script = taps(script, Input::Up, mins);
+    script = taps(script, Input::Start, 2);
+    script = taps(script, Input::Up, mode_steps);
+    script = taps(script, Input::Start, 1);
+    script = taps(script, Input::Up, increment);
+    script = taps(script, Input::Start, 1);
+    script = taps(script, Input::Up, increment);
+    // No later stages
+    script = taps(script, Input::Start, 2);
+    script = taps(script, Input::Up, overtime_steps);
+    script = taps(
+        script,
+        Input::Start,
+        match overtime_steps {
+            0 => 2,
+            1 => 4,
+            _ => 5,
+        },
+    );
+    (script, at)
+}
+
+#[test]
+fn bronstein_delay_gives_back_up_to_the_delay() {
+    // A minute each with a Bronstein delay of 5s
+    let (script, at) = custom(1, 1, 5, 0);
+    let frames = script
+        .tap(at + 1_000, Input::Down)
+        .wait(at + 4_000)
+        .tap(at + 4_100, Input::Down)
+        .tap(at + 12_000, Input::Up)
+        .run();
+    let frames = &frames[frames.len() - 4..];
+    // The main clock runs from the start, with the delay left alongside it
+    assert_eq!(frames[0].lines, ["[P1]d3 <<    P2 ", "0:00:58  0:01:00"]);
+    // P1 moved within the delay, so gets all of it back
+    assert_eq!(frames[2].lines, [" P1    >>   [P2]", "0:01:00  0:00:53"]);
+    assert_eq!(
+        frames[2].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 60_000,
+            move_number: 1,
+        }]
+    );
+    // P2 took 7.9s, and only gets the 5s of delay back
+    assert_eq!(frames[3].lines, ["[P1]d5 <<    P2 ", "0:01:00  0:00:58"]);
+    assert_eq!(
+        frames[3].sent,
+        [SerialMsg::StartP1 {
+            p2_time: 57_105,
+            move_number: 2,
+        }]
+    );
+}
+
+#[test]
+fn simple_delay_holds_the_clock_still() {
+    // A minute each with a simple delay of 5s
+    let (script, at) = custom(1, 2, 5, 0);
+    let frames = script
+        .tap(at + 1_000, Input::Down)
+        .wait(at + 4_000)
+        .tap(at + 4_100, Input::Down)
+        .tap(at + 12_000, Input::Up)
+        .run();
+    let frames = &frames[frames.len() - 4..];
+    // The main clock only runs once the delay is used up
+    assert_eq!(frames[0].lines, ["[P1]d3 <<    P2 ", "0:01:00  0:01:00"]);
+    assert_eq!(frames[2].lines, [" P1    >>   [P2]", "0:01:00  0:00:58"]);
+    assert_eq!(
+        frames[2].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 60_000,
+            move_number: 1,
+        }]
+    );
+    // P2 took 7.9s, of which the last 2.9s came off their clock
+    assert_eq!(frames[3].lines, ["[P1]d5 <<    P2 ", "0:01:00  0:00:58"]);
+    assert_eq!(
+        frames[3].sent,
+        [SerialMsg::StartP1 {
+            p2_time: 57_105,
+            move_number: 2,
+        }]
+    );
+}
+
+#[test]
+fn byo_yomi_periods_are_only_used_up_by_overrunning_them() {
+    // A minute each, then five byo-yomi periods of 30s
+    let (script, at) = custom(1, 0, 0, 1);
+    let frames = script
+        .tap(at + 1_000, Input::Down)
+        .wait(at + 62_000)
+        .tap(at + 80_000, Input::Down)
+        .tap(at + 82_000, Input::Up)
+        .wait(at + 113_000)
+        .wait(at + 245_000)
+        .run();
+    let frames = &frames[frames.len() - 6..];
+    // P1's main time ran out into their first period
+    assert_eq!(frames[0].lines, ["[P1]   <<    P2 ", "BY 5x30  0:01:00"]);
+    assert_eq!(
+        frames[0].sent[1..],
+        [SerialMsg::Sync {
+            p1_time: 30_000,
+            p2_time: 60_000,
+        }]
+    );
+    assert_eq!(frames[1].lines[1], "BY 5x12  0:01:00");
+    // Moving within the period starts the next one afresh
+    assert_eq!(frames[2].lines, [" P1    >>   [P2]", "BY 5x30  0:00:59"]);
+    assert_eq!(
+        frames[2].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 30_000,
+            move_number: 1,
+        }]
+    );
+    // Overrunning it uses it up
+    assert_eq!(frames[3].lines, ["[P1]   <<    P2 ", "BY 4x30  0:00:59"]);
+    assert_eq!(
+        frames[3].sent[1..],
+        [SerialMsg::Sync {
+            p1_time: 30_000,
+            p2_time: 58_005,
+        }]
+    );
+    // And overrunning the last one loses
+    assert_eq!(frames[4].sent.last(), Some(&SerialMsg::P1Finish));
+    assert_eq!(frames[5].lines, ["[P1]  Time's up!", "BY 1x00  0:00:59"]);
+}
+
+#[test]
+fn canadian_overtime_needs_its_moves_made_within_the_period() {
+    // A minute each, then blocks of 20 moves in 5 minutes
+    let (script, at) = custom(1, 0, 0, 2);
+    let mut script = script.tap(at + 1_000, Input::Down).wait(at + 62_000);
+    for n in 0..20 {
+        let move_at = at + 70_000 + n * 1_000;
+        script = script
+            .tap(move_at, Input::Down)
+            .tap(move_at + 500, Input::Up);
+    }
+    let frames = script.wait(at + 100_000).wait(at + 400_000).run();
+    let frames = &frames[frames.len() - 44..];
+    // P1's main time ran out into their first block
+    assert_eq!(frames[0].lines, ["[P1]   <<    P2 ", "20/5:00  0:01:00"]);
+    assert_eq!(
+        frames[0].sent[1..],
+        [SerialMsg::Sync {
+            p1_time: 300_000,
+            p2_time: 60_000,
+        }]
+    );
+    // Each move counts down the moves left, with the time carrying on
+    assert_eq!(frames[2].lines, [" P1    >>   [P2]", "19/4:52  0:01:00"]);
+    assert_eq!(
+        frames[2].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 291_005,
+            move_number: 1,
+        }]
+    );
+    assert_eq!(frames[38].lines[1], " 1/4:43  0:00:51");
+    // The last move of the block starts a fresh one
+    assert_eq!(frames[40].lines[1], "20/5:00  0:00:51");
+    assert_eq!(
+        frames[40].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 300_000,
+            move_number: 20,
+        }]
+    );
+    assert_eq!(frames[41].lines[1], "20/4:50  0:00:51");
+    // Running out of time with moves still to make loses
+    assert_eq!(frames[42].sent, [SerialMsg::P1Finish]);
+    assert_eq!(frames[42].lines[1], "20/0:00  0:00:51");
+}
+
+#[test]
+fn holding_start_through_the_splash_switches_to_big_digits() {
+    let (frames, eeprom) = Script::new()
+        .press(0, Input::Start)
+        .release(2_000, Input::Start)
+        .tap(7_000, Input::Start)
+        .tap(8_000, Input::Down)
+        .wait(9_000)
+        .run_with_eeprom();
+    assert_eq!(frames[1].lines[1], "Layout: big     ");
+    // Both players' 1:00, with the arrow pointing at P1
+    let big = ["  ▕.⊓⊓←    ▕.⊓⊓ ", "  ▕'⊔⊔     ▕'⊔⊔ "];
+    assert_eq!(frames[4].lines, big);
+
+    // The layout is remembered
+    let frames = Script::new()
+        .with_eeprom(&eeprom)
+        .tap(4_000, Input::Start)
+        .tap(5_000, Input::Down)
+        .wait(6_000)
+        .run();
+    assert_eq!(frames[2].lines, big);
+}
+
+#[test]
+fn segment_displays_show_tenths_under_ten_seconds() {
+    let frames = bullet()
+        .with_segments()
+        .wait(55_000)
+        .wait(60_000)
+        .wait(60_050)
+        .run();
+    // The waiting player's colon goes out, and P1's clock runs out at 65.015s
+    assert_eq!(frames[2].segments, Some([" 0:11".into(), " 1 00".into()]));
+    // Without a colon, the last decimal point shows P1's clock is running
+    assert_eq!(frames[3].segments, Some(["   5.1.".into(), " 1 00".into()]));
+    assert_eq!(frames[4].segments, Some(["   5.0.".into(), " 1 00".into()]));
+}
+
+#[test]
+fn half_a_message_doesnt_stall_the_clock() {
+    // The start of a Sync, with the rest lost on the way
+    let frames = bullet()
+        .send_bytes(10_000, &[0x0a, 0xc4, 0x01, 0x02])
+        .wait(15_000)
+        .run();
+    // P1's clock carried on from 5.015s to 15s
+    assert_eq!(frames[3].lines, ["[P1]   <<    P2 ", "0:00:51  0:01:00"]);
+}
+
+#[test]
+fn a_host_can_run_the_clock_as_its_slave() {
+    let frames = Script::new()
+        .tap(4_000, Input::Start)
+        .send(
+            4_500,
+            SerialMsg::Handshake {
+                mode: 0x0002,
+                sender: website(),
+            },
+        )
+        .tap(5_000, Input::Down)
+        .send(
+            6_000,
+            SerialMsg::StartP1 {
+                p2_time: 60_000,
+                move_number: 1,
+            },
+        )
+        .send(
+            16_000,
+            SerialMsg::StartP2 {
+                p1_time: 50_000,
+                move_number: 1,
+            },
+        )
+        .send(20_000, SerialMsg::Pause { time: 57_000 })
+        .send(
+            21_000,
+            SerialMsg::Sync {
+                p1_time: 40_000,
+                p2_time: 30_000,
+            },
+        )
+        .run();
+    // P1's button does nothing once the host is in charge
+    assert_eq!(frames[3].lines, [" P1/P2 to begin ", "0:01:00  0:01:00"]);
+    assert_eq!(frames[3].sent, []);
+    // Everything the host asks for is done and sent back
+    assert_eq!(frames[4].lines, ["[P1]   <<    P2 ", "0:00:51  0:01:00"]);
+    assert_eq!(
+        frames[4].sent,
+        [SerialMsg::StartP1 {
+            p2_time: 60_000,
+            move_number: 1,
+        }]
+    );
+    // Along with its times, rather than the clock's
+    assert_eq!(frames[5].lines, [" P1    >>   [P2]", "0:00:50  0:00:57"]);
+    assert_eq!(
+        frames[5].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 50_000,
+            move_number: 1,
+        }]
+    );
+    assert_eq!(frames[6].lines, [" P1  Paused  P2 ", "0:00:50  0:00:57"]);
+    assert_eq!(frames[6].sent, [SerialMsg::Pause { time: 57_000 }]);
+    assert_eq!(frames[7].lines, [" P1  Paused  P2 ", "0:00:40  0:00:30"]);
+    assert_eq!(
+        frames[7].sent,
+        [SerialMsg::Sync {
+            p1_time: 40_000,
+            p2_time: 30_000,
+        }]
+    );
+}
+
+#[test]
+fn a_host_in_sync_can_only_do_what_the_players_could() {
+    let frames = bullet()
+        .send(
+            6_000,
+            SerialMsg::Handshake {
+                mode: 0x0001,
+                sender: website(),
+            },
+        )
+        .send(
+            7_000,
+            SerialMsg::StartP1 {
+                p2_time: 30_000,
+                move_number: 1,
+            },
+        )
+        .tap(10_000, Input::Down)
+        .send(
+            12_000,
+            SerialMsg::StartP1 {
+                p2_time: 30_000,
+                move_number: 1,
+            },
+        )
+        .run();
+    // P1's clock is already running, so starting it again does nothing
+    assert_eq!(frames[4].lines, ["[P1]   <<    P2 ", "0:00:56  0:01:00"]);
+    assert_eq!(frames[4].sent, []);
+    assert_eq!(
+        frames[5].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 55_005,
+            move_number: 1,
+        }]
+    );
+    // Once P1 has moved it starts P1's clock again, but with the clock's own
+    // times
+    assert_eq!(frames[6].lines, ["[P1]   <<    P2 ", "0:00:55  0:00:59"]);
+    assert_eq!(
+        frames[6].sent,
+        [SerialMsg::StartP1 {
+            p2_time: 58_020,
+            move_number: 2,
+        }]
+    );
+}
+
+#[test]
+fn a_website_which_turns_up_late_gets_the_whole_game() {
+    let website = Identity {
+        capabilities: website().capabilities | CAPABILITY_RECONNECTS,
+        ..website()
+    };
+    let answer = SerialMsg::HandshakeResponse {
+        selected_mode: 0x0002,
+        sender: website,
+    };
+    let frames = bullet()
+        .unplugged()
+        .tap(9_000, Input::Down)
+        .send(12_000, answer)
+        .wait(14_500)
+        .send(18_000, answer)
+        .run();
+    // Nothing is sent while there's nobody there
+    assert_eq!(frames[3].lines, [" P1    >>   [P2]", "0:00:57  0:00:58"]);
+    assert_eq!(frames[3].sent, []);
+    // Answering one of the handshakes the clock keeps sending gets the game
+    // so far, and then a heartbeat every second
+    let game = |p1_time, p2_time| {
+        [
+            SerialMsg::Mode { mode: 0 },
+            SerialMsg::Increment {
+                p1_increment: 0,
+                p2_increment: 0,
+            },
+            SerialMsg::Sync { p1_time, p2_time },
+            SerialMsg::StageP1 { stage: 1 },
+            SerialMsg::StageP2 { stage: 1 },
+            SerialMsg::Phases {
+                p1_phase: 0,
+                p1_left: 0,
+                p2_phase: 0,
+                p2_left: 0,
+            },
+            SerialMsg::State {
+                state: 2,
+                p1_moves: 1,
+                p2_moves: 0,
+            },
+        ]
+    };
+    assert_eq!(frames[4].sent[..7], game(56_005, 57_015));
+    assert_eq!(frames[4].sent[7..], [SerialMsg::Heartbeat; 2]);
+    // The website never sent anything back, so the clock gave up on it after
+    // three seconds, and gets it back the same way
+    assert_eq!(frames[5].sent, [SerialMsg::Heartbeat]);
+    assert_eq!(frames[6].sent[..7], game(56_005, 51_015));
+}
+
+#[test]
+fn a_website_hears_the_times_set_and_when_overtime_starts() {
+    let website = Identity {
+        capabilities: website().capabilities | CAPABILITY_RECONNECTS,
+        ..website()
+    };
+    // Go 10m 5x30s is six along from Bullet 1+0, and the website only follows
+    // along as it usually does
+    let mut script = Script::new().send(
+        3_500,
+        SerialMsg::Handshake {
+            mode: 0x0003,
+            sender: website,
+        },
+    );
+    // Staying connected until P1 runs out of main time
+    for at in (4_000..=6_500).step_by(500) {
+        script = script
+            .tap(at, Input::Up)
+            .send(at + 200, SerialMsg::Heartbeat);
+    }
+    script = script.tap(7_000, Input::Start).tap(8_000, Input::Down);
+    for at in (9_000..=611_000).step_by(2_000) {
+        script = script.send(at, SerialMsg::Heartbeat);
+    }
+    let sent: Vec<_> = script
+        .run()
+        .into_iter()
+        .flat_map(|frame| frame.sent)
+        .collect();
+    // The times being set are where the game starts from
+    let setting = [
+        SerialMsg::Sync {
+            p1_time: 600_000,
+            p2_time: 600_000,
+        },
+        SerialMsg::State {
+            state: 0,
+            p1_moves: 0,
+            p2_moves: 0,
+        },
+    ];
+    assert!(sent.windows(2).any(|msgs| msgs == setting));
+    let byo_yomi = SerialMsg::Phases {
+        p1_phase: 1,
+        p1_left: 5,
+        p2_phase: 0,
+        p2_left: 0,
+    };
+    assert_eq!(sent.iter().filter(|&&msg| msg == byo_yomi).count(), 1);
+}
+
+#[test]
+fn an_hourglass_set_up_from_an_overtime_preset_has_no_overtime() {
+    // Play Go 10m 5x30s, six along from Bullet 1+0, to make it the last used
+    let mut script = Script::new();
+    for at in (4_000..=6_500).step_by(500) {
+        script = script.tap(at, Input::Up);
+    }
+    let frames = script
+        .tap(7_000, Input::Start)
+        .tap(8_000, Input::Start)
+        // Custom, which is just before it, starts from it
+        .tap(9_000, Input::Down)
+        .tap(10_000, Input::Start)
+        .tap(11_000, Input::Start)
+        .tap(12_000, Input::Start)
+        .tap(13_000, Input::Start)
+        .tap(14_000, Input::Start)
+        // Hourglass is just before Fischer
+        .tap(15_000, Input::Down)
+        .tap(16_000, Input::Start)
+        .tap(17_000, Input::Start)
+        .tap(18_000, Input::Down)
+        .wait(617_000)
+        .wait(619_000)
+        .run();
+    assert_eq!(frames[6].lines[1], "  Go 10m 5x30s  ");
+    // P1's ten minutes run out into P2's clock, with no byo-yomi after them
+    let end = frames.len() - 3;
+    assert_eq!(frames[end].lines, ["[P1]   <<    P2 ", "0:00:02  0:19:59"]);
+    assert_eq!(
+        frames[end + 1].lines,
+        ["[P1]  Time's up!", "0:00:00  0:20:00"]
+    );
+    assert_eq!(frames[end + 1].sent, [SerialMsg::P1Finish]);
+    assert!(frames
+        .iter()
+        .flat_map(|frame| &frame.sent)
+        .all(|msg| !matches!(msg, SerialMsg::Phases { p1_phase: 1.., .. })));
+}
+
+#[test]
+fn in_sync_the_clock_keeps_its_own_times_until_the_host_syncs() {
+    let frames = bullet()
+        .send(
+            6_000,
+            SerialMsg::Handshake {
+                mode: 0x0001,
+                sender: website(),
+            },
+        )
+        .send(8_000, SerialMsg::Pause { time: 10_000 })
+        .send(
+            9_000,
+            SerialMsg::StartP2 {
+                p1_time: 5_000,
+                move_number: 1,
+            },
+        )
+        .send(
+            11_000,
+            SerialMsg::Sync {
+                p1_time: 40_000,
+                p2_time: 30_000,
+            },
+        )
+        .send(13_000, SerialMsg::Pause { time: 20_000 })
+        .run();
+    // The host's pause and start only say what to do, so the clock keeps the
+    // times it had
+    assert_eq!(frames[4].lines, [" P1  Paused  P2 ", "0:00:58  0:01:00"]);
+    assert_eq!(frames[4].sent, [SerialMsg::Pause { time: 57_020 }]);
+    assert_eq!(frames[5].lines, [" P1    >>   [P2]", "0:00:58  0:00:59"]);
+    assert_eq!(
+        frames[5].sent,
+        [SerialMsg::StartP2 {
+            p1_time: 57_020,
+            move_number: 1,
+        }]
+    );
+    // But its Sync replaces both, with P2's clock carrying on from there
+    assert_eq!(frames[6].lines, [" P1    >>   [P2]", "0:00:40  0:00:28"]);
+    assert_eq!(
+        frames[6].sent,
+        [SerialMsg::Sync {
+            p1_time: 40_000,
+            p2_time: 30_000,
+        }]
+    );
+    assert_eq!(frames[7].lines, [" P1  Paused  P2 ", "0:00:40  0:00:28"]);
+    assert_eq!(frames[7].sent, [SerialMsg::Pause { time: 28_000 }]);
+}
@@ -0,0 +1,54 @@
+use open_chess_clock_sim::harness::{Input, Script};
+
+#[test]
+fn last_used_time_control_comes_first_next_time() {
+    // Rapid 10+5 is three along from Bullet 1+0
+    let (frames, eeprom) = Script::new()
+        .tap(4_000, Input::Up)
+        .tap(4_500, Input::Up)
+        .tap(5_000, Input::Up)
+        .tap(5_200, Input::Start)
+        .run_with_eeprom();
+    assert_eq!(frames[3].lines[1], "   Rapid 10+5   ");
+    assert_eq!(frames[4].lines[1], "0:10:00  0:10:00");
+
+    let frames = Script::new()
+        .with_eeprom(&eeprom)
+        .wait(4_000)
+        .tap(5_000, Input::Start)
+        .run();
+    assert_eq!(frames[0].lines, ["     Preset     ", "   Last used    "]);
+    assert_eq!(frames[2].lines[1], "0:10:00  0:10:00");
+}
+
+#[test]
+fn custom_keeps_the_overtime_it_starts_from() {
+    // Custom, through to the overtime, then byo-yomi 3x20s
+    let mut script = Script::new().tap(4_000, Input::Down);
+    for at in (4_300..=6_700).step_by(300) {
+        script = script.tap(at, Input::Start);
+    }
+    script = script
+        .tap(7_000, Input::Up)
+        .tap(7_300, Input::Start)
+        .tap(7_600, Input::Down)
+        .tap(7_900, Input::Down)
+        .tap(8_200, Input::Start);
+    for at in (8_500..=11_200).step_by(300) {
+        script = script.tap(at, Input::Down);
+    }
+    let (frames, eeprom) = script
+        .tap(11_500, Input::Start)
+        .tap(11_800, Input::Start)
+        .run_with_eeprom();
+    assert_eq!(frames[25].lines, ["    Byo-yomi    ", "     3 x 20s    "]);
+
+    // Going through Custom again keeps it
+    let mut script = Script::new().with_eeprom(&eeprom).tap(4_000, Input::Down);
+    for at in (4_300..=7_000).step_by(300) {
+        script = script.tap(at, Input::Start);
+    }
+    let frames = script.tap(7_300, Input::Start).run();
+    assert_eq!(frames[10].lines, ["    Overtime    ", "    Byo-yomi    "]);
+    assert_eq!(frames[11].lines, ["    Byo-yomi    ", "     3 x 20s    "]);
+}
@@ -0,0 +1,669 @@
+//! Every screen, compared with the golden frames in `tests/golden`. Each frame
+//! is the LCD's whole memory: the 16 characters shown on each line, then a
+//! `|`, then the 24 characters off the edge, which should always be blank. On
+//! a 20x4 LCD, which uses all of its memory, a frame is just its rows. On an
+//! OLED a frame is drawn with half blocks, two rows of pixels to a line, with
+//! the blank ends of lines left off. The segment displays facing each player
+//! are a line each.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the golden frames from what the screens
+//! show now, then check the diff.
+
+use std::{convert::Infallible, env, fs, path::PathBuf};
+
+use open_chess_clock_core::{
+    countdown::{Overtime, OvertimeMode, Phase, Stage, TimeControlMode, Turn},
+    display::{
+        Banner, CharLcd, ClockDisplay, Connection, Geometry, Layout, NoStatus, Oled, SegmentClocks,
+        SegmentsError, Setting,
+    },
+    presets::PRESET_COUNT,
+    storage::{SLOT_COUNT, USER_SLOTS},
+    time_set::{Increment, TimeSetPart, TimeSetting},
+};
+use open_chess_clock_sim::{
+    harness::{Input, Script},
+    lcd::{Ddram, InvalidPosition, VISIBLE_COLUMNS},
+    oled::{Gddram, InvalidPage},
+    segments::SegmentPair,
+};
+
+/// Screens collected to be compared with `tests/golden/<name>.txt`.
+struct Golden {
+    name: &'static str,
+    geometry: Geometry,
+    frames: String,
+}
+
+impl Golden {
+    fn new(name: &'static str) -> Golden {
+        Golden::with_geometry(name, Geometry::LCD_16X2)
+    }
+
+    fn with_geometry(name: &'static str, geometry: Geometry) -> Golden {
+        Golden {
+            name,
+            geometry,
+            frames: String::new(),
+        }
+    }
+
+    fn add(&mut self, title: &str, ddram: [&str; 2]) {
+        self.frames += &format!("# {title}\n");
+        for line in ddram {
+            // Custom characters are shown as blocks, which take more than a
+            // byte
+            let visible: String = line.chars().take(VISIBLE_COLUMNS).collect();
+            let hidden: String = line.chars().skip(VISIBLE_COLUMNS).collect();
+            self.frames += &format!("[{visible}|{hidden}]\n");
+        }
+    }
+
+    fn add_rows(&mut self, title: &str, rows: &[String]) {
+        self.frames += &format!("# {title}\n");
+        for row in rows {
+            self.frames += &format!("[{row}]\n");
+        }
+    }
+
+    fn add_pixels(&mut self, title: &str, rows: &[String]) {
+        self.frames += &format!("# {title}\n");
+        for row in rows {
+            self.frames += &format!("{}\n", row.trim_end());
+        }
+    }
+
+    /// Draws on a blank OLED.
+    fn draw_oled(
+        &mut self,
+        title: &str,
+        draw: impl FnOnce(&mut Oled<Gddram>) -> Result<(), InvalidPage>,
+    ) {
+        let mut display = Oled::new(Gddram::new());
+        draw(&mut display).unwrap();
+        self.add_pixels(title, &display.display().rows());
+    }
+
+    /// Draws on blank segment displays, with nothing else to show the rest.
+    fn draw_segments(
+        &mut self,
+        title: &str,
+        draw: impl FnOnce(
+            &mut SegmentClocks<SegmentPair, NoStatus>,
+        ) -> Result<(), SegmentsError<Infallible, Infallible>>,
+    ) {
+        let mut display = SegmentClocks::new(SegmentPair::new(), NoStatus);
+        draw(&mut display).unwrap();
+        self.frames += &format!("# {title}\n");
+        for player in [Turn::P1, Turn::P2] {
+            self.frames += &format!("{player:?} [{}]\n", display.segments.line(&player));
+        }
+    }
+
+    /// Draws on a blank LCD.
+    fn draw(
+        &mut self,
+        title: &str,
+        draw: impl FnOnce(&mut CharLcd<Ddram>) -> Result<(), InvalidPosition>,
+    ) {
+        let mut display = CharLcd::with_geometry(Ddram::new(), self.geometry);
+        draw(&mut display).unwrap();
+        if self.geometry == Geometry::LCD_16X2 {
+            self.add(title, [&display.lcd.line(0), &display.lcd.line(1)]);
+        } else {
+            self.add_rows(title, &display.lcd.rows(self.geometry));
+        }
+    }
+
+    /// Draws a set-up screen on a blank LCD.
+    fn setting(&mut self, title: &str, setting: Setting, blink_off_part: Option<TimeSetPart>) {
+        self.draw(title, |display| {
+            display.draw_setting(&setting, blink_off_part)
+        });
+    }
+
+    fn check(self) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.txt", self.name));
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&path, &self.frames).unwrap();
+            return;
+        }
+        let golden =
+            fs::read_to_string(&path).unwrap_or_else(|_| panic!("{} is missing", path.display()));
+        assert!(
+            golden == self.frames,
+            "{} doesn't match; got:\n{}",
+            path.display(),
+            self.frames
+        );
+    }
+}
+
+/// Adds every frame of a scripted run, titled by when it was taken.
+fn add_run(golden: &mut Golden, script: Script) {
+    for frame in script.run() {
+        golden.add(
+            &format!("{}ms", frame.at),
+            [&frame.ddram[0], &frame.ddram[1]],
+        );
+    }
+}
+
+#[test]
+fn start_up_screens() {
+    let mut golden = Golden::new("start_up");
+    golden.draw("splash", |display| display.draw_splash("1.2.0"));
+    for connection in [
+        Connection::Connecting,
+        Connection::Connected,
+        Connection::NotConnected,
+    ] {
+        golden.draw(&format!("{connection:?}"), |display| {
+            display.draw_splash("1.2.0")?;
+            display.draw_connection(connection)
+        });
+    }
+    for layout in [Layout::Standard, Layout::BigDigits] {
+        golden.draw(&format!("{layout:?}"), |display| {
+            display.draw_splash("1.2.0")?;
+            display.draw_layout(layout)
+        });
+    }
+    golden.draw("error", |display| display.draw_error("PinReadError"));
+    golden.check();
+}
+
+#[test]
+fn time_set_screens() {
+    let mut golden = Golden::new("time_set");
+    for index in 0..=SLOT_COUNT + PRESET_COUNT {
+        golden.setting(&format!("preset {index}"), Setting::Preset(index), None);
+    }
+    golden.setting(
+        "preset blinking",
+        Setting::Preset(0),
+        Some(TimeSetPart::Preset),
+    );
+
+    let times = [
+        (TimeSetting::new(0), TimeSetting::new(5)),
+        (TimeSetting::new(90 * 60), TimeSetting::new(59 * 60 + 59)),
+        (
+            TimeSetting::new(TimeSetting::MAX_TIME),
+            TimeSetting::new(10 * 60),
+        ),
+    ];
+    for (p1, p2) in &times {
+        golden.setting("time", Setting::Times(p1, p2), None);
+    }
+    for part in [
+        TimeSetPart::P1SetMin,
+        TimeSetPart::P1SetSec,
+        TimeSetPart::P2SetMin,
+        TimeSetPart::P2SetSec,
+    ] {
+        golden.setting(
+            &format!("time blinking {part:?}"),
+            Setting::Times(&times[1].0, &times[1].1),
+            Some(part),
+        );
+    }
+
+    let modes = [
+        TimeControlMode::Fischer,
+        TimeControlMode::Bronstein,
+        TimeControlMode::SimpleDelay,
+        TimeControlMode::Hourglass,
+    ];
+    for mode in &modes {
+        golden.setting(&format!("mode {mode:?}"), Setting::Mode(mode), None);
+    }
+    golden.setting(
+        "mode blinking",
+        Setting::Mode(&modes[0]),
+        Some(TimeSetPart::SetMode),
+    );
+
+    let (five, thirty) = (Increment::new(5), Increment::new(30));
+    for mode in &modes[..3] {
+        golden.setting(
+            &format!("increment {mode:?}"),
+            Setting::Increments(&five, &thirty, mode),
+            None,
+        );
+    }
+    let (most, none) = (Increment::new(Increment::MAX_INCREMENT), Increment::new(0));
+    for part in [TimeSetPart::P1SetInc, TimeSetPart::P2SetInc] {
+        golden.setting(
+            &format!("increment blinking {part:?}"),
+            Setting::Increments(&most, &none, &modes[0]),
+            Some(part),
+        );
+    }
+
+    let stages = [
+        Stage::new(),
+        Stage {
+            after_moves: 40,
+            time: TimeSetting::new(30 * 60),
+            increment: Increment::new(30),
+        },
+        Stage {
+            after_moves: 5,
+            time: TimeSetting::new(15),
+            increment: Increment::new(5),
+        },
+    ];
+    for (index, stage) in stages.iter().enumerate() {
+        golden.setting(
+            &format!("stage {index}"),
+            Setting::Stage {
+                index: index % 2,
+                stage,
+                mode: &modes[index % 3],
+            },
+            None,
+        );
+    }
+    for part in [
+        TimeSetPart::StageMoves,
+        TimeSetPart::StageMin,
+        TimeSetPart::StageSec,
+        TimeSetPart::StageInc,
+    ] {
+        golden.setting(
+            &format!("stage blinking {part:?}"),
+            Setting::Stage {
+                index: 0,
+                stage: &stages[1],
+                mode: &modes[0],
+            },
+            Some(part),
+        );
+    }
+
+    let no_overtime = Overtime::new(OvertimeMode::None);
+    for blink_off_part in [None, Some(TimeSetPart::SetOvertime)] {
+        golden.setting(
+            &format!("overtime None blinking {blink_off_part:?}"),
+            Setting::Overtime {
+                part: TimeSetPart::SetOvertime,
+                overtime: &no_overtime,
+            },
+            blink_off_part,
+        );
+    }
+    // The count and period screens only come up for the kinds with settings
+    for mode in [OvertimeMode::ByoYomi, OvertimeMode::Canadian] {
+        let overtime = Overtime::new(mode);
+        for part in [TimeSetPart::SetOvertime, TimeSetPart::OvertimeCount] {
+            golden.setting(
+                &format!("overtime {mode:?} {part:?}"),
+                Setting::Overtime {
+                    part,
+                    overtime: &overtime,
+                },
+                None,
+            );
+        }
+        for part in [
+            TimeSetPart::OvertimeCount,
+            TimeSetPart::OvertimeMin,
+            TimeSetPart::OvertimeSec,
+        ] {
+            golden.setting(
+                &format!("overtime {mode:?} blinking {part:?}"),
+                Setting::Overtime {
+                    part: TimeSetPart::OvertimeCount,
+                    overtime: &overtime,
+                },
+                Some(part),
+            );
+        }
+        // Byo-yomi periods only go up to 99s
+        let most = Overtime {
+            count: 99,
+            period: TimeSetting::new(if mode == OvertimeMode::Canadian {
+                59 * 60 + 59
+            } else {
+                99
+            }),
+            ..overtime
+        };
+        golden.setting(
+            &format!("overtime {mode:?} most"),
+            Setting::Overtime {
+                part: TimeSetPart::OvertimeCount,
+                overtime: &most,
+            },
+            None,
+        );
+    }
+
+    for slot in 0..=USER_SLOTS {
+        golden.setting(
+            &format!("save preset {slot}"),
+            Setting::SavePreset(slot),
+            None,
+        );
+    }
+    golden.setting(
+        "save preset blinking",
+        Setting::SavePreset(1),
+        Some(TimeSetPart::SavePreset),
+    );
+    golden.check();
+}
+
+#[test]
+fn countdown_screens() {
+    let mut golden = Golden::new("countdown");
+    let p1 = TimeSetting::new(5 * 60 + 7);
+    let p2 = TimeSetting::new(TimeSetting::MAX_TIME);
+    let main = (Phase::Main, Phase::Main);
+    for turn in [Turn::P1, Turn::P2] {
+        for delay in [None, Some(5), Some(15)] {
+            for stage_note in [None, Some(1)] {
+                golden.draw(
+                    &format!("{turn:?} delay {delay:?} stage {stage_note:?}"),
+                    |display| display.draw_countdown(&turn, delay, stage_note, &p1, &p2, &main),
+                );
+            }
+        }
+    }
+
+    let phases = [
+        (
+            Phase::ByoYomi { periods: 3 },
+            Phase::ByoYomi { periods: 12 },
+        ),
+        (Phase::Canadian { moves: 12 }, Phase::Canadian { moves: 5 }),
+    ];
+    let overtime_times = [
+        (TimeSetting::new(24), TimeSetting::from_millis(4_001)),
+        (TimeSetting::new(4 * 60 + 59), TimeSetting::new(15 * 60)),
+        (TimeSetting::new(60), TimeSetting::new(200 * 60)),
+    ];
+    for phases in phases {
+        for (p1, p2) in overtime_times {
+            let title = format!("{phases:?} {}ms {}ms", p1.into_millis(), p2.into_millis());
+            golden.draw(&title, |display| display.draw_times(&p1, &p2, &phases));
+        }
+    }
+    golden.check();
+}
+
+#[test]
+fn big_digit_screens() {
+    let mut golden = Golden::new("big_digits");
+    let main = (Phase::Main, Phase::Main);
+    let times = [
+        TimeSetting::new(5 * 60 + 7),
+        TimeSetting::new(9),
+        TimeSetting::new(59 * 60 + 59),
+        TimeSetting::new(60 * 60 + 5 * 60 + 9),
+        TimeSetting::new(TimeSetting::MAX_TIME),
+    ];
+    for turn in [Turn::P1, Turn::P2] {
+        for time in &times {
+            golden.draw(&format!("{turn:?} {}s", time.into_secs()), |display| {
+                display.set_layout(Layout::BigDigits)?;
+                display.draw_countdown(&turn, None, None, time, time, &main)
+            });
+        }
+        for (delay, stage_note) in [(Some(5), None), (Some(15), Some(1)), (None, Some(1))] {
+            // Both clocks, then only the running one with an hour on the other
+            for p1_time in [&times[0], &times[3]] {
+                golden.draw(
+                    &format!(
+                        "{turn:?} delay {delay:?} stage {stage_note:?} P1 {}s",
+                        p1_time.into_secs()
+                    ),
+                    |display| {
+                        display.set_layout(Layout::BigDigits)?;
+                        display.draw_countdown(&turn, delay, stage_note, p1_time, &times[1], &main)
+                    },
+                );
+            }
+        }
+    }
+    // Overtime is shown the standard way
+    golden.draw("byo-yomi", |display| {
+        display.set_layout(Layout::BigDigits)?;
+        display.draw_countdown(
+            &Turn::P1,
+            None,
+            None,
+            &times[1],
+            &times[0],
+            &(Phase::ByoYomi { periods: 3 }, Phase::Main),
+        )
+    });
+    golden.check();
+}
+
+#[test]
+fn banners() {
+    let mut golden = Golden::new("banners");
+    let moves = [(0, 0), (9, 10), (99, 100), (1000, 999)];
+    let banners = [
+        Banner::Begin,
+        Banner::Cancel,
+        Banner::Players,
+        Banner::Paused,
+        Banner::Restart,
+        Banner::Resume,
+        Banner::TimeUp(Turn::P1),
+        Banner::TimeUp(Turn::P2),
+    ]
+    .into_iter()
+    .chain(moves.iter().map(Banner::Moves));
+    for banner in banners {
+        golden.draw(&format!("{banner:?}"), |display| {
+            display.draw_banner(&banner)
+        });
+    }
+    golden.check();
+}
+
+/// Picks Bullet 1+0, the first preset on a fresh clock, and starts P1's clock
+/// at 5s.
+fn bullet() -> Script {
+    Script::new()
+        .tap(4_000, Input::Start)
+        .tap(5_000, Input::Down)
+}
+
+#[test]
+fn pause_screens() {
+    let mut golden = Golden::new("pause");
+    // The banners cycle before the game, and again once paused after a move
+    // each
+    let mut script = Script::new().tap(4_000, Input::Start);
+    for at in (4_500..12_000).step_by(1_000) {
+        script = script.wait(at);
+    }
+    script = script
+        .tap(12_000, Input::Down)
+        .tap(13_000, Input::Down)
+        .tap(14_000, Input::Up)
+        .tap(15_000, Input::Start);
+    for at in (16_000..26_000).step_by(1_000) {
+        script = script.wait(at);
+    }
+    add_run(&mut golden, script);
+    golden.check();
+}
+
+#[test]
+fn finish_screens() {
+    let mut golden = Golden::new("finish");
+    add_run(&mut golden, bullet().wait(65_100).wait(67_000));
+    add_run(
+        &mut golden,
+        bullet().tap(6_000, Input::Down).wait(66_100).wait(68_000),
+    );
+    golden.check();
+}
+
+#[test]
+fn lcd_20x4_screens() {
+    let mut golden = Golden::with_geometry("lcd_20x4", Geometry::LCD_20X4);
+    golden.draw("splash", |display| {
+        display.draw_splash("1.2.0")?;
+        display.draw_connection(Connection::Connected)
+    });
+    let (p1, p2) = (TimeSetting::new(5 * 60 + 7), TimeSetting::new(3 * 60));
+    golden.setting("time", Setting::Times(&p1, &p2), None);
+    golden.setting("preset", Setting::Preset(SLOT_COUNT), None);
+
+    let main = (Phase::Main, Phase::Main);
+    let (five, thirty) = (Increment::new(5), Increment::new(30));
+    for mode in [
+        TimeControlMode::Fischer,
+        TimeControlMode::Bronstein,
+        TimeControlMode::Hourglass,
+    ] {
+        golden.draw(&format!("countdown {mode:?}"), |display| {
+            display.draw_details(&(12, 11), (&five, &thirty), &mode)?;
+            display.draw_countdown(&Turn::P2, Some(3), None, &p1, &p2, &main)
+        });
+    }
+    golden.draw("big digits", |display| {
+        display.set_layout(Layout::BigDigits)?;
+        display.draw_details(&(12, 11), (&five, &thirty), &TimeControlMode::Fischer)?;
+        display.draw_countdown(&Turn::P1, None, Some(1), &p1, &p2, &main)
+    });
+    golden.draw("paused", |display| {
+        display.draw_times(&p1, &p2, &main)?;
+        display.draw_details(&(12, 11), (&five, &thirty), &TimeControlMode::Fischer)?;
+        display.draw_banner(&Banner::Paused)
+    });
+    golden.draw("error", |display| display.draw_error("PinReadError"));
+
+    // The rows under the clocks are redrawn going from screen to screen
+    let script = bullet()
+        .tap(6_000, Input::Down)
+        .tap(7_000, Input::Start)
+        .tap(8_000, Input::Start)
+        .tap(9_000, Input::Start);
+    for frame in script.with_geometry(Geometry::LCD_20X4).run() {
+        golden.add_rows(&format!("{}ms", frame.at), &frame.lines);
+    }
+    golden.check();
+}
+
+#[test]
+fn oled_screens() {
+    let mut golden = Golden::new("oled");
+    golden.draw_oled("splash", |display| {
+        display.draw_splash("1.2.0")?;
+        display.draw_connection(Connection::Connected)
+    });
+    let (p1, p2) = (TimeSetting::new(5 * 60 + 7), TimeSetting::new(3 * 60));
+    golden.draw_oled("time", |display| {
+        display.draw_setting(&Setting::Times(&p1, &p2), None)
+    });
+
+    let start = (TimeSetting::new(10 * 60), TimeSetting::new(10 * 60));
+    let main = (Phase::Main, Phase::Main);
+    let five = Increment::new(5);
+    for turn in [Turn::P1, Turn::P2] {
+        golden.draw_oled(&format!("countdown {turn:?}"), |display| {
+            display.set_start_times(&start.0, &start.1)?;
+            display.draw_details(&(12, 11), (&five, &five), &TimeControlMode::Fischer)?;
+            display.draw_countdown(&turn, Some(3), None, &p1, &p2, &main)
+        });
+    }
+    // Long clocks shrink to fit
+    let overtime = (Phase::ByoYomi { periods: 3 }, Phase::Canadian { moves: 12 });
+    golden.draw_oled("overtime", |display| {
+        display.set_start_times(&start.0, &start.1)?;
+        display.draw_countdown(
+            &Turn::P1,
+            None,
+            Some(1),
+            &TimeSetting::new(2 * 60 * 60),
+            &TimeSetting::new(4 * 60 + 59),
+            &overtime,
+        )
+    });
+    golden.draw_oled("paused", |display| {
+        display.set_start_times(&start.0, &start.1)?;
+        display.draw_times(&p1, &p2, &main)?;
+        display.draw_details(&(12, 11), (&five, &five), &TimeControlMode::Fischer)?;
+        display.draw_banner(&Banner::Paused)
+    });
+    golden.draw_oled("error", |display| display.draw_error("PinReadError"));
+
+    // The whole display is cleared going between the set-up screens and the
+    // clocks
+    let script = bullet()
+        .tap(6_000, Input::Down)
+        .tap(7_000, Input::Start)
+        .tap(8_000, Input::Start);
+    for frame in script.with_oled().run() {
+        golden.add_pixels(&format!("{}ms", frame.at), &frame.lines);
+    }
+    golden.check();
+}
+
+#[test]
+fn segment_screens() {
+    let mut golden = Golden::new("segments");
+    golden.draw_segments("splash", |display| display.draw_splash("1.2.0"));
+    let (p1, p2) = (TimeSetting::new(5 * 60 + 7), TimeSetting::new(25 * 60));
+    golden.draw_segments("time", |display| {
+        display.draw_setting(&Setting::Times(&p1, &p2), None)
+    });
+    golden.draw_segments("time blinking", |display| {
+        display.draw_setting(&Setting::Times(&p1, &p2), Some(TimeSetPart::P2SetMin))
+    });
+    golden.draw_segments("preset", |display| {
+        display.draw_setting(&Setting::Preset(0), None)
+    });
+
+    let main = (Phase::Main, Phase::Main);
+    let (short, long) = (TimeSetting::from_millis(9_301), TimeSetting::new(90 * 60));
+    for turn in [Turn::P1, Turn::P2] {
+        golden.draw_segments(&format!("countdown {turn:?}"), |display| {
+            display.draw_countdown(&turn, None, None, &p1, &p2, &main)
+        });
+        golden.draw_segments(&format!("countdown {turn:?} tenths and hours"), |display| {
+            display.draw_countdown(&turn, None, None, &short, &long, &main)
+        });
+    }
+    let overtime = (
+        Phase::ByoYomi { periods: 3 },
+        Phase::ByoYomi { periods: 12 },
+    );
+    golden.draw_segments("byo-yomi", |display| {
+        display.draw_countdown(
+            &Turn::P2,
+            None,
+            None,
+            &TimeSetting::new(30),
+            &short,
+            &overtime,
+        )
+    });
+    golden.draw_segments("paused", |display| display.draw_times(&short, &long, &main));
+    golden.draw_segments("error", |display| display.draw_error("PinReadError"));
+
+    // The LCD carries on showing everything else
+    let script = bullet()
+        .tap(6_000, Input::Down)
+        .tap(7_000, Input::Start)
+        .with_segments();
+    for frame in script.run() {
+        golden.add(
+            &format!("{}ms", frame.at),
+            [&frame.ddram[0], &frame.ddram[1]],
+        );
+        let [p1, p2] = frame.segments.unwrap();
+        golden.frames += &format!("P1 [{p1}]\nP2 [{p2}]\n");
+    }
+    golden.check();
+}
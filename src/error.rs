@@ -1,6 +0,0 @@
-#[derive(ufmt::derive::uDebug)]
-pub enum RuntimeError {
-    LcdError,
-    PinReadError,
-    PinWriteError,
-}
@@ -2,6 +2,16 @@
 
 type Player = "p1" | "p2";
 
+/**
+ * The overtime a player is in after their main time, and how much of it they
+ * have left: the byo-yomi periods including the current one, or the moves
+ * left to make in the Canadian block
+ */
+export interface Overtime {
+  kind: "byoYomi" | "canadian";
+  left: number;
+}
+
 export default class Clock {
   private p1TimeStart: number = 0;
   private p1TimeStartDate: Date | null = null;
@@ -11,12 +21,48 @@ export default class Clock {
   p1TimeInitial = 0;
   p2TimeInitial = 0;
 
+  /**
+   * The ms of increment or delay each player gets for their move
+   */
+  p1Increment = 0;
+  p2Increment = 0;
+  /**
+   * Whether the increments are actually (Bronstein or simple) delays
+   */
+  isDelay = false;
+  /**
+   * Whether the time the running player uses goes onto their opponent's clock
+   */
+  isHourglass = false;
+
+  /**
+   * The stage of the time control each player is in, counting from 1
+   */
+  p1Stage = 1;
+  p2Stage = 1;
+
+  /**
+   * The overtime each player is in, or null while they're on their main time
+   */
+  p1Overtime: Overtime | null = null;
+  p2Overtime: Overtime | null = null;
+
+  /**
+   * The move each player is on (or was on, if it's not their turn), counting
+   * from 1
+   */
+  p1MoveNumber = 1;
+  p2MoveNumber = 1;
+
   loser: Player | null = null;
 
   readonly = false;
 
   get p1Time() {
     if (this.p1TimeStartDate === null) {
+      if (this.isHourglass && this.p2TimeStartDate !== null) {
+        return this.p1TimeStart + (this.p2TimeStart - this.p2Time);
+      }
       return this.p1TimeStart;
     }
     let time =
@@ -35,6 +81,9 @@ export default class Clock {
 
   get p2Time() {
     if (this.p2TimeStartDate === null) {
+      if (this.isHourglass && this.p1TimeStartDate !== null) {
+        return this.p2TimeStart + (this.p1TimeStart - this.p1Time);
+      }
       return this.p2TimeStart;
     }
     let time =
@@ -57,9 +106,12 @@ export default class Clock {
   }
 
   pause() {
-    this.p1TimeStart = this.p1Time;
+    // Read both first since in hourglass mode each depends on the other
+    const p1Time = this.p1Time;
+    const p2Time = this.p2Time;
+    this.p1TimeStart = p1Time;
     this.p1TimeStartDate = null;
-    this.p2TimeStart = this.p2Time;
+    this.p2TimeStart = p2Time;
     this.p2TimeStartDate = null;
   }
 
@@ -75,6 +127,8 @@ export default class Clock {
   startPlayer(player: Player, time: number | null = null) {
     if (player === "p1") {
       if (this.p1TimeStartDate === null) {
+        // Keep any time gained in hourglass mode
+        this.p1TimeStart = this.p1Time;
         this.p1TimeStartDate = new Date();
       }
       if (time !== null) {
@@ -84,6 +138,8 @@ export default class Clock {
       this.p2TimeStartDate = null;
     } else {
       if (this.p2TimeStartDate === null) {
+        // Keep any time gained in hourglass mode
+        this.p2TimeStart = this.p2Time;
         this.p2TimeStartDate = new Date();
       }
       if (time !== null) {
@@ -43,8 +43,9 @@ async function runSerial() {
   await port.open({
     baudRate: SERIAL_BAUD_RATE,
   });
+  // The clock sends a handshake when it starts, and answers ours if it's
+  // already running
   const serialHandler = new JsSerialHandler(port);
-  // We don't do a handshake here because we expect the hardware to
   runtime(new SerialClock(serialHandler));
 }
 
@@ -1,7 +1,8 @@
 // @ts-check
 
-import Clock from "./clock";
+import Clock, { Overtime } from "./clock";
 import { expectEl } from "./main";
+import { CAPABILITY_MOVE_COUNTERS, formatVersion } from "./serial";
 import SerialClock from "./serialclock";
 import "./style.css";
 
@@ -22,11 +23,13 @@ const p1TimeElements = [
   document.querySelector(".p1.counter .minutes"),
   document.querySelector(".p1.counter .seconds"),
 ].map((x) => expectEl(x));
+const p1Info = expectEl(document.querySelector(".p1.counter .info"));
 const p2Counter = expectEl(document.querySelector(".p2.counter"));
 const p2TimeElements = [
   document.querySelector(".p2.counter .minutes"),
   document.querySelector(".p2.counter .seconds"),
 ].map((x) => expectEl(x));
+const p2Info = expectEl(document.querySelector(".p2.counter .info"));
 
 const pauseButton = expectEl(document.querySelector("#pause-button"));
 const restartButton = expectEl(document.querySelector("#restart-button"));
@@ -34,6 +37,32 @@ const status = expectEl(document.querySelector(".status"));
 const hint = expectEl(document.querySelector("#hint"));
 const controls = expectEl(document.querySelector("#controls"));
 
+function formatInfo(
+  incrementMs: number,
+  isDelay: boolean,
+  stage: number,
+  overtime: Overtime | null,
+  moveNumber: number | null
+) {
+  const parts: string[] = [];
+  if (moveNumber !== null) {
+    parts.push(`Move ${moveNumber}`);
+  }
+  if (overtime !== null) {
+    parts.push(
+      overtime.kind === "byoYomi"
+        ? `byo-yomi, ${overtime.left} left`
+        : `${overtime.left} moves to go`
+    );
+  } else if (incrementMs > 0) {
+    parts.push(`${isDelay ? "delay " : "+"}${Math.floor(incrementMs / 1000)}s`);
+  }
+  if (stage > 1) {
+    parts.push(`stage ${stage}`);
+  }
+  return parts.join(" · ");
+}
+
 export function runtime(clock: Clock) {
   status.textContent = "";
   if (clock.readonly) {
@@ -50,7 +79,9 @@ export function runtime(clock: Clock) {
   const updateInterval = setInterval(() => {
     if (clock instanceof SerialClock) {
       status.textContent = clock.connected
-        ? READONLY_STATUS
+        ? clock.peer?.version
+          ? `${READONLY_STATUS} (v${formatVersion(clock.peer.version)})`
+          : READONLY_STATUS
         : READONLY_CONNECTING_STATUS;
       if (clock.connected) {
         counterContainer.classList.remove("show-connecting");
@@ -75,6 +106,24 @@ export function runtime(clock: Clock) {
       (clock.p2Time / clock.p2TimeInitial).toString()
     );
     const currentPlayer = clock.currentPlayer;
+    // Clocks which don't count moves send meaningless move numbers
+    const countsMoves =
+      !(clock instanceof SerialClock) ||
+      ((clock.peer?.capabilities ?? 0) & CAPABILITY_MOVE_COUNTERS) !== 0;
+    p1Info.textContent = formatInfo(
+      clock.p1Increment,
+      clock.isDelay,
+      clock.p1Stage,
+      clock.p1Overtime,
+      countsMoves && currentPlayer === "p1" ? clock.p1MoveNumber : null
+    );
+    p2Info.textContent = formatInfo(
+      clock.p2Increment,
+      clock.isDelay,
+      clock.p2Stage,
+      clock.p2Overtime,
+      countsMoves && currentPlayer === "p2" ? clock.p2MoveNumber : null
+    );
     if (currentPlayer === "p1") {
       p1Counter.classList.add("active");
       p2Counter.classList.remove("active");
@@ -119,14 +168,18 @@ export function runtime(clock: Clock) {
   });
   p1Counter.addEventListener("click", () => {
     hasStarted = true;
+    if (clock.currentPlayer === "p1") clock.p1MoveNumber += 1;
     clock.startPlayer(clock.currentPlayer === null ? "p1" : "p2");
   });
   p2Counter.addEventListener("click", () => {
     hasStarted = true;
+    if (clock.currentPlayer === "p2") clock.p2MoveNumber += 1;
     clock.startPlayer(clock.currentPlayer === null ? "p2" : "p1");
   });
   restartButton.addEventListener("click", () => {
     hasStarted = false;
+    clock.p1MoveNumber = 1;
+    clock.p2MoveNumber = 1;
     clock.updateTimes(clock.p1TimeInitial, clock.p2TimeInitial);
     clock.pause();
   });
@@ -1,25 +1,101 @@
+/**
+ * Ends every frame. COBS makes sure it's never found inside one.
+ */
+const DELIMITER = 0x00;
+/**
+ * The longest frame received before COBS: a message type, four arguments and
+ * the CRC, with room for newer versions of the protocol to add up to four more
+ * arguments to any message.
+ */
+const MAX_FRAME = 1 + 8 * 4 + 2;
+
+/**
+ * The version of /PROTOCOL.md spoken here. Version 1 was unframed.
+ */
+export const PROTOCOL_VERSION = 2;
+
+/**
+ * Sends `increment` and `mode` along with the times.
+ */
+export const CAPABILITY_INCREMENTS = 1 << 0;
+/**
+ * Counts moves, so the move numbers in `startP1` and `startP2` mean something.
+ */
+export const CAPABILITY_MOVE_COUNTERS = 1 << 1;
+/**
+ * Sends `stageP1` and `stageP2` for time controls with several stages.
+ */
+export const CAPABILITY_STAGES = 1 << 2;
+/**
+ * Follows the `startP1`, `startP2`, `pause` and `sync` it receives.
+ */
+export const CAPABILITY_REMOTE_CONTROL = 1 << 3;
+/**
+ * Sends `heartbeat`s and drops the connection when they stop coming back, and
+ * sends `phases` and `state` so a game can be picked up partway through.
+ */
+export const CAPABILITY_RECONNECTS = 1 << 4;
+
+/**
+ * Everything the website understands.
+ */
+const CAPABILITIES =
+  CAPABILITY_INCREMENTS |
+  CAPABILITY_MOVE_COUNTERS |
+  CAPABILITY_STAGES |
+  CAPABILITY_RECONNECTS;
+
+/**
+ * How often a `heartbeat` is sent while connected, or a `handshake` while not,
+ * in ms.
+ */
+export const HEARTBEAT_MS = 1000;
+/**
+ * How long without hearing anything before the clock counts as gone, in ms.
+ */
+const DISCONNECT_MS = 3 * HEARTBEAT_MS;
+
+/**
+ * Who's at the other end of the connection, as they say in their handshake.
+ */
+export interface Identity {
+  protocolVersion: number;
+  capabilities: number;
+  /**
+   * Their software version as `0xMMmmpp`, or 0 if they don't have one
+   */
+  version: number;
+}
+
+/**
+ * Unpacks a software version from a handshake into `major.minor.patch`.
+ */
+export function formatVersion(version: number): string {
+  return [version >> 16, (version >> 8) & 0xff, version & 0xff].join(".");
+}
+
 /**
  * The types of sendable messages.
- * Keep up to date with /src/serial.rs
+ * Keep up to date with /core/src/serial.rs and /PROTOCOL.md
  *
- * Note: all messages are big-endian
+ * Note: all arguments are big-endian
  */
 const messages = Object.freeze({
   handshake: {
     hex: 0xc0,
-    arguments: 1,
+    arguments: 4,
   },
   handshakeResponse: {
     hex: 0xc1,
-    arguments: 1,
+    arguments: 4,
   },
   startP1: {
     hex: 0xc2,
-    arguments: 1,
+    arguments: 2,
   },
   startP2: {
     hex: 0xc3,
-    arguments: 1,
+    arguments: 2,
   },
   sync: {
     hex: 0xc4,
@@ -37,14 +113,169 @@ const messages = Object.freeze({
     hex: 0xc7,
     arguments: 0,
   },
+  increment: {
+    hex: 0xc8,
+    arguments: 2,
+  },
+  mode: {
+    hex: 0xc9,
+    arguments: 1,
+  },
+  stageP1: {
+    hex: 0xca,
+    arguments: 1,
+  },
+  stageP2: {
+    hex: 0xcb,
+    arguments: 1,
+  },
+  heartbeat: {
+    hex: 0xcc,
+    arguments: 0,
+  },
+  phases: {
+    hex: 0xcd,
+    arguments: 4,
+  },
+  state: {
+    hex: 0xce,
+    arguments: 3,
+  },
 } satisfies Record<string, { hex: number; arguments: number }>);
 
+/**
+ * CRC-16/CCITT-FALSE, with the polynomial 0x1021 starting from 0xffff.
+ */
+function crc16(bytes: Uint8Array): number {
+  let crc = 0xffff;
+  for (const byte of bytes) {
+    crc ^= byte << 8;
+    for (let bit = 0; bit < 8; bit++) {
+      crc = crc & 0x8000 ? ((crc << 1) ^ 0x1021) & 0xffff : (crc << 1) & 0xffff;
+    }
+  }
+  return crc;
+}
+
+/**
+ * Encodes `frame` with COBS, so there's no zero in it, and adds a delimiter.
+ */
+function encodeFrame(frame: Uint8Array): Uint8Array {
+  const encoded: number[] = [];
+  // Frames are much shorter than COBS's 254-byte limit, so every block ends at
+  // a zero or at the end of the frame
+  let blockStart = 0;
+  for (let i = 0; i <= frame.length; i++) {
+    if (i === frame.length || frame[i] === DELIMITER) {
+      encoded.push(i - blockStart + 1, ...frame.subarray(blockStart, i));
+      blockStart = i + 1;
+    }
+  }
+  encoded.push(DELIMITER);
+  return new Uint8Array(encoded);
+}
+
+/**
+ * Undoes COBS a byte at a time, collecting a frame until its delimiter.
+ */
+class FrameDecoder {
+  private frame: number[] = [];
+  /**
+   * How many bytes are left in the current COBS block, which is 0 when the
+   * next byte starts a new one.
+   */
+  private remaining = 0;
+  /**
+   * Whether a zero goes between the current block and the next one.
+   */
+  private zeroNext = false;
+
+  /**
+   * Takes the next byte off the wire. If it's a delimiter, returns the frame
+   * it ends unless that frame can't possibly be whole.
+   */
+  push(byte: number): Uint8Array | undefined {
+    if (byte === DELIMITER) {
+      // A delimiter in the middle of a block means bytes were lost
+      const whole = this.remaining === 0 && this.frame.length <= MAX_FRAME;
+      const frame = new Uint8Array(this.frame);
+      this.frame = [];
+      this.remaining = 0;
+      this.zeroNext = false;
+      return whole ? frame : undefined;
+    }
+    if (this.remaining === 0) {
+      if (this.zeroNext) this.frame.push(0);
+      // 0xff is the only code whose block isn't followed by a zero
+      this.remaining = byte - 1;
+      this.zeroNext = byte !== 0xff;
+    } else {
+      // Anything longer is junk, so there's no need to keep all of it
+      if (this.frame.length <= MAX_FRAME) this.frame.push(byte);
+      this.remaining--;
+    }
+    return undefined;
+  }
+}
+
+/**
+ * Reads a message from a frame if its CRC is right and it's one we know with
+ * at least the arguments we know about.
+ */
+function decodeFrame(
+  frame: Uint8Array
+): [keyof typeof messages, number[]] | undefined {
+  if (frame.length < 3) return undefined;
+  const dv = new DataView(frame.buffer, frame.byteOffset, frame.byteLength);
+  const body = frame.subarray(0, frame.length - 2);
+  if (dv.getUint16(frame.length - 2, false) !== crc16(body)) {
+    console.warn("dropped serial frame with a bad CRC");
+    return undefined;
+  }
+  const msgId = dv.getUint8(0);
+  const message = Object.entries(messages).find(
+    ([_, { hex }]) => hex === msgId
+  )?.[0] as keyof typeof messages | undefined;
+  // Newer versions of the protocol can add arguments on the end, which we
+  // ignore, but they're always whole
+  const argsEnd =
+    message === undefined ? 0 : 1 + messages[message].arguments * 4;
+  if (
+    message === undefined ||
+    body.length < argsEnd ||
+    (body.length - 1) % 4 !== 0
+  ) {
+    console.warn("dropped unknown serial message:", msgId);
+    return undefined;
+  }
+  const args: number[] = [];
+  for (let offset = 1; offset < argsEnd; offset += 4) {
+    args.push(
+      dv.getUint32(
+        offset,
+        false // big-endian
+      )
+    );
+  }
+  return [message, args];
+}
+
 /**
  * Handles serial communication between the firmware and website.
- * Keep up to date with /src/serial.rs
+ * Keep up to date with /core/src/serial.rs and /PROTOCOL.md
  */
 export class SerialHandler {
   connected = false;
+  /**
+   * Who's on the other end, once the handshake is done
+   */
+  peer: Identity | undefined;
+  private decoder = new FrameDecoder();
+  private received: [keyof typeof messages, number[]][] = [];
+  /**
+   * When something last arrived, from `Date.now()`
+   */
+  private lastHeard = 0;
 
   constructor(private port: SerialPort) {}
 
@@ -55,11 +286,12 @@ export class SerialHandler {
     if (args.length !== messages[message].arguments) {
       throw new Error("provided arguments does not match spec");
     }
-    const buffer = new ArrayBuffer(
+    const frame = new Uint8Array(
       1 + // the msg header
-        args.length * 4 // one u32 (4 bytes) per argument
+        args.length * 4 + // one u32 (4 bytes) per argument
+        2 // the CRC
     );
-    const dv = new DataView(buffer, 0, buffer.byteLength);
+    const dv = new DataView(frame.buffer);
     dv.setUint8(0, messages[message].hex);
     args.forEach((arg, i) => {
       dv.setUint32(
@@ -68,72 +300,48 @@ export class SerialHandler {
         false // big-endian
       );
     });
-    await writer.write(new Uint8Array(buffer));
+    dv.setUint16(
+      frame.length - 2,
+      crc16(frame.subarray(0, frame.length - 2)),
+      false // big-endian
+    );
+    await writer.write(encodeFrame(frame));
     writer.releaseLock();
     console.info(`wrote ${message}:`, args);
   }
 
+  /**
+   * Waits for the next message which arrives whole, dropping any frames which
+   * were garbled on the way.
+   */
   private async rawRead(): Promise<[keyof typeof messages, number[]]> {
-    const read = async (): Promise<[keyof typeof messages, number[]]> => {
-      const reader = this.port.readable?.getReader({
-        mode: "byob",
-      });
-      if (!reader) throw new Error("failed to lock serial writer");
-      const { value: headerValue } = await reader.read(
-        new Uint8Array(new ArrayBuffer(1), 0, 1)
-      );
-      if (!headerValue) throw new Error("stream ended early");
-      const headerDv = new DataView(headerValue.buffer);
-      const msgId = headerDv.getUint8(0);
-      const message = Object.entries(messages).find(
-        ([_, { hex }]) => hex === msgId
-      )?.[0] as keyof typeof messages | undefined;
-      if (message === undefined) {
-        console.warn("unknown serial message header:", msgId);
-        // wait for a little, flush reader for some amount of bytes then try again
-        await new Promise((r) => setTimeout(r, 100));
-        await reader.read(new Uint8Array(256));
-        reader.releaseLock();
-        return await read();
-      }
-      if (messages[message].arguments > 0) {
-        // Wait for a little to let the clock finish sending the bytes
-        await new Promise((r) => setTimeout(r, 100));
-        const argsLength = messages[message].arguments * 4;
-        const argumentsBuffer = new ArrayBuffer(argsLength);
-        const { value: argumentsValue } = await reader.read(
-          new Uint8Array(argumentsBuffer, 0, argsLength)
-        );
-        if (!argumentsValue) throw new Error("stream ended early");
-        const argumentsDv = new DataView(argumentsValue.buffer);
-        const args: number[] = [];
-        for (let offset = 0; offset < argsLength; offset += 4) {
-          args.push(
-            argumentsDv.getUint32(
-              offset,
-              false // big-endian
-            )
-          );
-        }
-        reader.releaseLock();
-        console.info(`read ${message}:`, args);
-        return [message, args];
-      } else {
-        reader.releaseLock();
-        console.info(`read ${message}`);
-        return [message, []];
+    while (this.received.length === 0) {
+      const reader = this.port.readable?.getReader();
+      if (!reader) throw new Error("failed to lock serial reader");
+      const { value, done } = await reader.read();
+      reader.releaseLock();
+      if (done || !value) throw new Error("stream ended early");
+      for (const byte of value) {
+        const frame = this.decoder.push(byte);
+        const message = frame && decodeFrame(frame);
+        if (message) this.received.push(message);
       }
-    };
-    return await read();
+    }
+    this.lastHeard = Date.now();
+    const [message, args] = this.received.shift()!;
+    console.info(`read ${message}:`, args);
+    return [message, args];
   }
 
   async read(): Promise<[keyof typeof messages, number[]]> {
     const read = async (): Promise<[keyof typeof messages, number[]]> => {
       const [msg, args] = await this.rawRead();
       if (msg === "handshake") {
-        this.connected = true;
+        // Both sides speak the older protocol, and we don't speak anything
+        // older than ours
+        const protocolVersion = Math.min(args[1], PROTOCOL_VERSION);
         let selectedMode = 0x0000;
-        switch (args[0]) {
+        switch (protocolVersion === PROTOCOL_VERSION ? args[0] : undefined) {
           case 0x0000: {
             // We decide
             selectedMode = 0x0003; // make other party master
@@ -159,14 +367,35 @@ export class SerialHandler {
             break;
           }
         }
-        this.write("handshakeResponse", [selectedMode]);
+        this.connected = selectedMode !== 0x0000;
+        this.peer = this.connected
+          ? { protocolVersion, capabilities: args[2], version: args[3] }
+          : undefined;
+        this.write("handshakeResponse", [
+          selectedMode,
+          protocolVersion,
+          CAPABILITIES,
+          0, // the website doesn't have a version
+        ]);
+        if (this.peer) {
+          console.info(
+            `connected to clock v${formatVersion(this.peer.version)}`,
+            this.peer
+          );
+        }
         // retry
         return await read();
       } else if (msg === "handshakeResponse") {
-        this.connected = true;
-        if (args[0] === 0x0000) {
-          // they said our mode is unsupported
+        if (args[0] === 0x0000 || args[1] !== PROTOCOL_VERSION) {
+          // they said our mode or protocol is unsupported
           console.error("can't negotiate mode");
+        } else {
+          this.connected = true;
+          this.peer = {
+            protocolVersion: args[1],
+            capabilities: args[2],
+            version: args[3],
+          };
         }
         // retry
         return await read();
@@ -177,6 +406,37 @@ export class SerialHandler {
     return await read();
   }
 
+  /**
+   * Keeps the connection going, so call it every `HEARTBEAT_MS`. Sends a
+   * heartbeat while connected, and drops the connection if nothing has arrived
+   * for three. While not connected, sends a handshake instead, so a clock which
+   * is already running can answer it.
+   */
+  async keepAlive() {
+    // Older clocks don't send heartbeats, so they can't be missed
+    const reconnects =
+      ((this.peer?.capabilities ?? 0) & CAPABILITY_RECONNECTS) !== 0;
+    if (
+      this.connected &&
+      reconnects &&
+      Date.now() - this.lastHeard >= DISCONNECT_MS
+    ) {
+      console.warn("clock stopped answering");
+      this.connected = false;
+      this.peer = undefined;
+    }
+    if (!this.connected) {
+      await this.write("handshake", [
+        0x0003, // the clock is the master
+        PROTOCOL_VERSION,
+        CAPABILITIES,
+        0, // the website doesn't have a version
+      ]);
+    } else if (reconnects) {
+      await this.write("heartbeat");
+    }
+  }
+
   async close() {
     this.port.close();
   }
@@ -206,5 +466,6 @@ export class JsSerialHandler extends SerialHandler {
       }
     };
     readLoop();
+    setInterval(() => this.keepAlive(), HEARTBEAT_MS);
   }
 }
@@ -1,5 +1,19 @@
-import Clock from "./clock";
-import { JsSerialHandler } from "./serial";
+import Clock, { Overtime } from "./clock";
+import { CAPABILITY_RECONNECTS, JsSerialHandler } from "./serial";
+
+/**
+ * Reads a player's phase and what's left of it from `phases`
+ */
+function overtime(phase: number, left: number): Overtime | null {
+  switch (phase) {
+    case 0x0001:
+      return { kind: "byoYomi", left };
+    case 0x0002:
+      return { kind: "canadian", left };
+    default:
+      return null;
+  }
+}
 
 export default class SerialClock extends Clock {
   readonly = true;
@@ -11,21 +25,41 @@ export default class SerialClock extends Clock {
     switch (message) {
       case "startP1": {
         this.p2Time = args[0];
+        this.p1MoveNumber = args[1];
         this.startPlayer("p1");
         break;
       }
       case "startP2": {
         this.p1Time = args[0];
+        this.p2MoveNumber = args[1];
         this.startPlayer("p2");
         break;
       }
       case "sync": {
-        if (this.currentPlayer === null) {
-          // game stopped. update initial times too
-          this.p1TimeInitial = args[0];
-          this.p2TimeInitial = args[1];
-        }
         this.updateTimes(args[0], args[1]);
+        // Clocks which send `state` say when these are the times the game
+        // starts from, so a paused game isn't mistaken for a new one
+        if (this.currentPlayer === null && !this.sendsState) {
+          this.startFromTimes();
+        }
+        break;
+      }
+      case "increment": {
+        this.p1Increment = args[0];
+        this.p2Increment = args[1];
+        break;
+      }
+      case "mode": {
+        this.isDelay = args[0] === 0x0001 || args[0] === 0x0002;
+        this.isHourglass = args[0] === 0x0003;
+        break;
+      }
+      case "stageP1": {
+        this.p1Stage = args[0];
+        break;
+      }
+      case "stageP2": {
+        this.p2Stage = args[0];
         break;
       }
       case "p1Finish": {
@@ -42,13 +76,84 @@ export default class SerialClock extends Clock {
         this.pause();
         break;
       }
+      case "phases": {
+        this.p1Overtime = overtime(args[0], args[1]);
+        this.p2Overtime = overtime(args[2], args[3]);
+        break;
+      }
+      case "state": {
+        // Picking the game up partway through, with the times just synced
+        this.p1MoveNumber = args[1] + 1;
+        this.p2MoveNumber = args[2] + 1;
+        switch (args[0]) {
+          case 0x0000: {
+            this.pause();
+            this.startFromTimes();
+            break;
+          }
+          case 0x0001: {
+            this.startPlayer("p1");
+            break;
+          }
+          case 0x0002: {
+            this.startPlayer("p2");
+            break;
+          }
+          case 0x0005: {
+            this.loser = "p1";
+            this.p1Time = 0;
+            break;
+          }
+          case 0x0006: {
+            this.loser = "p2";
+            this.p2Time = 0;
+            break;
+          }
+          default: {
+            this.pause();
+            break;
+          }
+        }
+        break;
+      }
     }
   };
 
+  /**
+   * Starts a new game from the times just synced
+   */
+  private startFromTimes() {
+    this.p1TimeInitial = this.p1Time;
+    this.p2TimeInitial = this.p2Time;
+    this.p1Stage = 1;
+    this.p2Stage = 1;
+    this.p1Overtime = null;
+    this.p2Overtime = null;
+    this.p1MoveNumber = 1;
+    this.p2MoveNumber = 1;
+  }
+
+  /**
+   * Whether the clock sends `state`, which older ones don't
+   */
+  private get sendsState() {
+    return (
+      ((this.serialHandler.peer?.capabilities ?? 0) & CAPABILITY_RECONNECTS) !==
+      0
+    );
+  }
+
   get connected() {
     return this.serialHandler.connected;
   }
 
+  /**
+   * Who the clock says it is, once it's connected
+   */
+  get peer() {
+    return this.serialHandler.peer;
+  }
+
   constructor(private serialHandler: JsSerialHandler) {
     super(0, 0);
     console.log(this);